@@ -0,0 +1,79 @@
+use std::{hint::black_box, time::{Duration, Instant}};
+use projup::file::{self, ParserData};
+
+mod previous;
+
+const RUNS: u32 = 20;
+
+/// Text with some of the keys spread through it
+fn source(len: usize) -> String
+{
+    let words = ["lorem", "ipsum", "PROJECT_NAME", "dolor", "sit", "amet", "AUTHOR", "consectetur", "PROJ", "elit"];
+    let mut text = String::with_capacity(len);
+    let mut i = 0;
+    while text.len() < len
+    {
+        text.push_str(words[(i * 7) % words.len()]);
+        text.push(if i % 12 == 11 { '\n' } else { ' ' });
+        i += 1;
+    }
+    
+    return text;
+}
+fn keys(count: usize) -> Vec<(String, String)>
+{
+    let mut keys = vec![("PROJECT_NAME".to_string(), "my_project".to_string()),
+        ("PROJ".to_string(), "mp".to_string()),
+        ("AUTHOR".to_string(), "someone".to_string())];
+    for i in 0..count
+    {
+        keys.push((format!("KEY_{i}_VALUE"), format!("value {i}")));
+    }
+    // needed by the previous matcher
+    keys.sort_by(|a, b| a.0.cmp(&b.0));
+    
+    return keys;
+}
+
+fn time<F>(mut f: F) -> Duration
+    where F: FnMut()
+{
+    // warm up
+    f();
+    
+    let start = Instant::now();
+    for _ in 0..RUNS
+    {
+        f();
+    }
+    return start.elapsed() / RUNS;
+}
+
+fn main()
+{
+    for (len, count) in [(64 * 1024, 0), (64 * 1024, 100), (4 * 1024 * 1024, 0), (4 * 1024 * 1024, 100)]
+    {
+        let text = source(len);
+        let keys = keys(count);
+        
+        let pd = ParserData::new(&keys);
+        let ppd = previous::ParserData::new(&keys);
+        assert_eq!(file::parse(&text, &pd), previous::parse(&text, &ppd));
+        
+        let build = time(|| { black_box(ParserData::new(black_box(&keys))); });
+        let automaton = time(|| { black_box(file::parse(black_box(&text), &pd)); });
+        let stream = time(||
+        {
+            let mut result = Vec::with_capacity(text.len());
+            file::parse_stream(black_box(text.as_bytes()), &mut result, &pd, file::STREAM_CAPACITY).unwrap();
+            black_box(result);
+        });
+        let old = time(|| { black_box(previous::parse(black_box(&text), &ppd)); });
+        
+        println!("{} bytes, {} keys", len, keys.len());
+        println!("    build:     {:?}", build);
+        println!("    automaton: {:?}", automaton);
+        println!("    stream:    {:?}", stream);
+        println!("    previous:  {:?}", old);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,53 @@
+use std::path::Path;
+use log::{info, warn};
+use projup::{error::ProjUpError, file, missing_path};
+use crate::{cli::ApplyArgs, git};
+use super::{commit_message, commit_project, find_template, load_backups, load_template_to_source, prompt_missing_variables, warn_subtrees};
+
+pub fn apply(mut args: ApplyArgs) -> Result<(), ProjUpError>
+{
+    let file = file::get_projects_path()?;
+    let b = load_backups(&file)?;
+    
+    let location = b.try_get_source(&args.name)
+        .ok_or_else(|| ProjUpError::UnkownProject(args.name.clone()))?;
+    
+    if !Path::new(location).exists()
+    {
+        return missing_path!(location.into());
+    }
+    
+    // only the changes of the template are committed
+    if args.commit.is_some() && !git::is_clean(location)?
+    {
+        return Err(ProjUpError::UncommittedChanges(args.name.clone()));
+    }
+    
+    let t_path = find_template(&args.template)?;
+    prompt_missing_variables(&t_path, &mut args.variables, &args.name)?;
+    let message = match &args.commit
+    {
+        Some(m) => Some(commit_message(m, &args.name, Some(&args.template), &args.variables)?),
+        None => None
+    };
+    let (hooks, subtrees) = load_template_to_source(&t_path, location, &args.variables, &args.name, args.conflict)?;
+    // hooks are only run when creating a project
+    if !hooks.is_empty()
+    {
+        warn!("Skipped {} hooks of template \"{}\"", hooks.len(), args.template);
+    }
+    
+    match message
+    {
+        Some(m) =>
+        {
+            // projects without a backup yet are pushed by the backup command
+            let push = b.can_backup() && b.iter().any(|(n, _, imm)| *n == args.name && !imm);
+            commit_project(location, &m, push, &subtrees)?;
+        },
+        None => warn_subtrees(&subtrees)
+    }
+    
+    info!("Successfully loaded template \"{}\" into \"{}\"", args.template, &args.name);
+    return Ok(());
+}
\ No newline at end of file
@@ -1,7 +1,5 @@
-use std::fs;
-
 use log::info;
-use projup::{error::{HandleProjUpError, IntoProjUpError, ProjUpError}, file};
+use projup::{error::{HandleProjUpError, IntoProjUpError, ProjUpError}, file::{self, ops}};
 
 use crate::{cli::BackupArgs, git};
 use super::{create_backup, load_backups, BACKUP_REMOTE};
@@ -21,7 +19,7 @@ pub fn backup(args: BackupArgs) -> Result<(), ProjUpError>
     {
         if *imm
         {
-            if create_backup(backup, args.force, &project).handle()
+            if create_backup(backup, args.force, &project, None).handle()
             {
                 *imm = false;
                 edit = true;
@@ -39,7 +37,7 @@ pub fn backup(args: BackupArgs) -> Result<(), ProjUpError>
     
     if edit
     {
-        fs::write(&file, b.to_content()).projup(&file)?;
+        ops::write_registry(&file, b.to_content()).projup(&file)?;
     }
     
     return Ok(());
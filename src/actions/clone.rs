@@ -21,7 +21,7 @@ pub fn clone(args: CloneArgs) -> Result<(), ProjUpError>
         return missing_path!(project);
     }
     
-    git::run(git::GitOperation::Clone { url: &project, path: args.path.as_deref() }, "./")?;
+    git::run(git::GitOperation::Clone { url: &project, path: args.path.as_deref(), reference: None, depth: None }, "./")?;
     
     return Ok(());
 }
\ No newline at end of file
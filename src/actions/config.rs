@@ -1,5 +1,4 @@
-use std::fs;
-use projup::{data::Backups, error::{HandleProjUpError, IntoProjUpError, ProjUpError}, file::{self, traverse}};
+use projup::{data::Backups, error::{HandleProjUpError, IntoProjUpError, ProjUpError}, file::{self, ops, traverse}};
 use crate::{cli::ConfigArgs, git};
 
 use super::{load_backups, load_templates, BACKUP_REMOTE};
@@ -20,7 +19,7 @@ pub fn config(mut args: ConfigArgs) -> Result<(), ProjUpError>
         
         t.set_location(&nl)?;
         
-        fs::write(&file, t.to_content()).projup(&file)?;
+        ops::write_registry(&file, t.to_content()).projup(&file)?;
     }
     if let Some(nl) = args.backup_location
     {
@@ -46,12 +45,13 @@ pub fn config(mut args: ConfigArgs) -> Result<(), ProjUpError>
             Err(e) => return Err(e)
         };
         
+        // read before set_location overwrites it, as the backups are moved from here
+        let old = b.get_location().clone();
         b.set_location(&nl)?;
         
         if !args.soft
         {
-            let old = b.get_location();
-            traverse::try_move(old, &nl).projup(old)?;
+            traverse::try_move(&old, &nl).projup(&old)?;
             
             // change all git remote locations
             for (n, l, imm) in b.iter()
@@ -66,7 +66,7 @@ pub fn config(mut args: ConfigArgs) -> Result<(), ProjUpError>
             }
         }
         
-        fs::write(&file, b.to_content()).projup(&file)?;
+        ops::write_registry(&file, b.to_content()).projup(&file)?;
     }
     
     return Ok(());
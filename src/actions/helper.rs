@@ -1,6 +1,6 @@
 use std::{fs, path::PathBuf};
 
-use projup::{data::{Backups, Templates}, error::{IntoProjUpError, ProjUpError}, file};
+use projup::{data::{Backups, Templates}, error::{IntoProjUpError, ProjUpError}, file::{self, ops}};
 
 pub fn load_templates(file: &PathBuf) -> Result<Templates, ProjUpError>
 {
@@ -30,7 +30,7 @@ pub fn load_templates(file: &PathBuf) -> Result<Templates, ProjUpError>
             None => return Err(ProjUpError::ProgramFolder)
         };
         // ensure folder exists
-        fs::create_dir_all(&location).projup(&location)?;
+        ops::create_dir_all(&location).projup(&location)?;
         
         let location = match location.to_str()
         {
@@ -0,0 +1,49 @@
+use std::{path::Path, process::Command};
+use log::{info, warn};
+use projup::{data::{Hook, HookFailure}, error::ProjUpError, file::ops};
+
+/// Runs each hook in `directory` through the shell, in order
+pub(crate) fn run_hooks(hooks: &[Hook], directory: impl AsRef<Path>) -> Result<(), ProjUpError>
+{
+    for h in hooks
+    {
+        if ops::is_dry_run()
+        {
+            info!("Would run hook \"{}\" in {}", h.command, directory.as_ref().display());
+            continue;
+        }
+        
+        info!("Running hook \"{}\"", h.command);
+        let error = match shell(&h.command).current_dir(&directory).status()
+        {
+            Ok(s) if s.success() => continue,
+            Ok(s) => s.to_string(),
+            Err(e) => e.to_string()
+        };
+        
+        match h.on_error
+        {
+            HookFailure::Fail => return Err(ProjUpError::HookError(h.command.clone(), error)),
+            HookFailure::Warn => warn!("Hook \"{}\" failed: {}", h.command, error),
+            HookFailure::Ignore => {}
+        }
+    }
+    
+    return Ok(());
+}
+
+fn shell(command: &str) -> Command
+{
+    #[cfg(windows)]
+    {
+        let mut c = Command::new("cmd");
+        c.arg("/C").arg(command);
+        return c;
+    }
+    #[cfg(not(windows))]
+    {
+        let mut c = Command::new("sh");
+        c.arg("-c").arg(command);
+        return c;
+    }
+}
\ No newline at end of file
@@ -1,12 +1,16 @@
 mod templates;
 mod config;
 mod helper;
+mod transaction;
 mod new;
 mod backup;
 mod remove;
 mod r#move;
 mod ls;
 mod clone;
+mod apply;
+mod template_sources;
+mod hooks;
 
 pub use templates::*;
 pub use config::*;
@@ -16,6 +20,10 @@ pub use remove::*;
 pub use r#move::*;
 pub use ls::*;
 pub use clone::*;
+pub use apply::*;
 use helper::*;
+use transaction::*;
+use template_sources::*;
+use hooks::*;
 
 const BACKUP_REMOTE: &str = "local-backup";
\ No newline at end of file
@@ -1,6 +1,5 @@
-use std::fs;
 use log::info;
-use projup::{error::{IntoProjUpError, ProjUpError}, file::{self, traverse}, path_exists};
+use projup::{error::{IntoProjUpError, ProjUpError}, file::{self, ops, traverse}, path_exists};
 use crate::{cli::MoveArgs, git};
 use super::{load_backups, BACKUP_REMOTE};
 
@@ -35,7 +34,7 @@ pub fn r#move(args: MoveArgs) -> Result<(), ProjUpError>
                 return path_exists!(backups.1);
             }
             
-            fs::remove_dir_all(&backups.1).projup(&backups.1)?;
+            ops::remove_dir_all(&backups.1).projup(&backups.1)?;
         }
         
         traverse::try_move(&backups.0, &backups.1).projup(&backups.0)?;
@@ -46,7 +45,7 @@ pub fn r#move(args: MoveArgs) -> Result<(), ProjUpError>
             }, &args.destination)?;
     }
     
-    fs::write(&file, b.to_content()).projup(&file)?;
+    ops::write_registry(&file, b.to_content()).projup(&file)?;
     info!("Successfully moved {} to {}", args.source.display(), args.destination.display());
     return Ok(());
 }
\ No newline at end of file
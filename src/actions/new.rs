@@ -1,22 +1,64 @@
-use std::{fs, path::PathBuf};
+use std::path::{Path, PathBuf};
 use log::{info, warn};
-use projup::{error::{IntoProjUpError, ProjUpError}, file, missing_path, path_exists};
-use crate::{cli::{NewArgs, NewExistingArgs}, git};
-use super::{find_template, load_backups, load_template_to_source, BACKUP_REMOTE};
+use projup::{data::{ConfigArgs, Dependency}, error::{HandleProjUpError, IntoProjUpError, ProjUpError}, file::{self, ops}, missing_path, path_exists};
+use crate::{cli::{Conflict, NewArgs, NewExistingArgs}, git};
+use super::{add_subtree, check_variables, find_template, first_missing, load_backups, load_template_to_source, prompt_missing_variables, run_hooks, Step, Transaction, BACKUP_REMOTE};
 
-pub fn new(args: NewArgs) -> Result<(), ProjUpError>
+/// Added to the name of a backup replaced with `--force` while the new project is created
+const REPLACED_SUFFIX: &str = ".replaced";
+
+pub fn new(mut args: NewArgs) -> Result<(), ProjUpError>
 {
     if args.name.exists()
     {
         return path_exists!(args.name);
     }
     
+    // find template before anything is created
+    let template = match &args.template
+    {
+        Some(t) => Some(find_template(t)?),
+        None => None
+    };
+    let name = args.name.file_name().unwrap_or_default().to_string_lossy().to_string();
+    if let Some(t_path) = &template
+    {
+        prompt_missing_variables(t_path, &mut args.variables, &name)?;
+        check_variables(t_path, &args.variables, &name)?;
+    }
+    let message = match &args.commit
+    {
+        Some(m) => Some(commit_message(m, &name, args.template.as_deref(), &args.variables)?),
+        None => None
+    };
+    
+    let mut transaction = Transaction::new();
+    let result = create_project(&args, template.as_deref(), message.as_deref(), &mut transaction);
+    
+    if result.is_err()
+    {
+        if !args.keep_on_error
+        {
+            transaction.rollback();
+            return result;
+        }
+        warn!("Project was only partially created");
+    }
+    
+    transaction.finish();
+    return result;
+}
+
+fn create_project(args: &NewArgs, template: Option<&Path>, message: Option<&str>, transaction: &mut Transaction) -> Result<(), ProjUpError>
+{
     let file = file::get_projects_path()?;
     let mut b = load_backups(&file)?;
     let can_backup = b.can_backup();
     
     // create folder for project
-    fs::create_dir_all(&args.name).projup(&args.name)?;
+    let created = first_missing(&args.name);
+    ops::create_dir_all(&args.name).projup(&args.name)?;
+    transaction.push(Step::CreatedDir(created));
     // add to projects collection
     let name = b.try_add_name(&args.name, can_backup)?;
     
@@ -28,7 +70,7 @@ pub fn new(args: NewArgs) -> Result<(), ProjUpError>
     {
         // will exist
         let path = b.try_get_backup(name).unwrap();
-        create_backup(path, args.force, &location)?;
+        create_backup(path, args.force, &location, Some(&mut *transaction))?;
     }
     else
     {
@@ -36,22 +78,97 @@ pub fn new(args: NewArgs) -> Result<(), ProjUpError>
     }
     
     // write out new backups
-    fs::write(&file, b.to_content()).projup(&file)?;
+    ops::write_registry(&file, b.to_content()).projup(&file)?;
+    transaction.push(Step::Registered(name.to_string()));
     
     // Template stuff
-    if let Some(template) = args.template
+    let subtrees = match (template, &args.template)
+    {
+        (Some(t_path), Some(template)) =>
+        {
+            let (hooks, subtrees) = load_template_to_source(t_path, &location, &args.variables, name, Conflict::Overwrite)?;
+            if args.no_hooks
+            {
+                if !hooks.is_empty()
+                {
+                    warn!("Skipped {} hooks of template \"{}\"", hooks.len(), template);
+                }
+            }
+            else
+            {
+                run_hooks(&hooks, &location)?;
+            }
+            subtrees
+        },
+        _ => Vec::new()
+    };
+    
+    match message
+    {
+        Some(m) => commit_project(&location, m, can_backup, &subtrees)?,
+        None => warn_subtrees(&subtrees)
+    }
+    
+    match &args.template
+    {
+        Some(template) => info!("Successfully created \"{}\" into {} from template \"{}\"", name, &location, template),
+        None => info!("Successfully created \"{}\" into {}", name, &location)
+    }
+    return Ok(());
+}
+
+/// Replaces the variables in the commit `message`, where `$template` is the name of the loaded template
+pub(crate) fn commit_message(message: &str, name: &str, template: Option<&str>, variables: &[(String, String)]) -> Result<String, ProjUpError>
+{
+    let mut args = ConfigArgs::new(name);
+    for v in variables
+    {
+        args.map.insert(&v.0, &v.1);
+    }
+    if let Some(t) = template
+    {
+        args.map.entry("template").or_insert(t);
+    }
+    
+    return file::replace_variables(message, &mut args).map_err(ProjUpError::InvalidCommitMessage);
+}
+
+/// Stages all files of the project at `location` and commits them, pushing to the backup if `push`.
+/// The copies of `subtrees` are replaced by subtree merges after the commit
+pub(crate) fn commit_project(location: &str, message: &str, push: bool, subtrees: &[Dependency]) -> Result<(), ProjUpError>
+{
+    for d in subtrees
+    {
+        let path = Path::new(location).join(&d.path);
+        ops::remove_dir_all(&path).projup(&path)?;
+    }
+    git::run(git::GitOperation::Add, location)?;
+    git::run(git::GitOperation::Commit { message }, location)?;
+    for d in subtrees
     {
-        let t_path = find_template(&template)?;
-        load_template_to_source(&t_path, &location, &args.variables, name)?;
-        info!("Successfully created \"{}\" into {} from template \"{}\"", name, &location, template);
-        return Ok(());
+        add_subtree(d, Path::new(location))?;
     }
     
-    info!("Successfully created \"{}\" into {}", name, &location);
+    // the commit is kept even if the backup fails
+    if push && git::run(git::GitOperation::Push { force: false, remote: BACKUP_REMOTE }, location).handle()
+    {
+        info!("Pushed commit \"{}\" to backup", message);
+    }
     return Ok(());
 }
 
-pub(crate) fn create_backup(path: PathBuf, force: bool, location: &str) -> Result<(), ProjUpError>
+/// Warns that `subtrees` are only copies when the project is not committed
+pub(crate) fn warn_subtrees(subtrees: &[Dependency])
+{
+    if !subtrees.is_empty()
+    {
+        warn!("Copied {} subtree dependencies without a subtree merge, which needs --commit", subtrees.len());
+    }
+}
+
+/// The created backup folder is recorded in `transaction` if given,
+/// along with any existing backup, which is moved aside rather than deleted so that it can be restored
+pub(crate) fn create_backup(path: PathBuf, force: bool, location: &str, mut transaction: Option<&mut Transaction>) -> Result<(), ProjUpError>
 {
     // backup folder already exists
     // - could be due to leftover project
@@ -62,9 +179,30 @@ pub(crate) fn create_backup(path: PathBuf, force: bool, location: &str) -> Resul
             return path_exists!(path);
         }
         
-        fs::remove_dir_all(&path).projup(&path)?;
+        match transaction.as_deref_mut()
+        {
+            Some(t) =>
+            {
+                let mut aside = path.clone().into_os_string();
+                aside.push(REPLACED_SUFFIX);
+                let aside = PathBuf::from(aside);
+                if aside.exists()
+                {
+                    return path_exists!(aside);
+                }
+                
+                ops::rename(&path, &aside).projup(&path)?;
+                t.push(Step::Replaced(path.clone(), aside));
+            },
+            None => ops::remove_dir_all(&path).projup(&path)?
+        }
+    }
+    let created = first_missing(&path);
+    ops::create_dir_all(&path).projup(&path)?;
+    if let Some(t) = transaction
+    {
+        t.push(Step::CreatedDir(created));
     }
-    fs::create_dir_all(&path).projup(&path)?;
     git::run(git::GitOperation::Init { bare: true }, &path)?;
     
     
@@ -98,7 +236,7 @@ pub fn new_existing(args: NewExistingArgs) -> Result<(), ProjUpError>
     {
         // will exist
         let path = b.try_get_backup(name).unwrap();
-        create_backup(path, args.force, &location)?;
+        create_backup(path, args.force, &location, None)?;
     }
     else
     {
@@ -112,7 +250,7 @@ pub fn new_existing(args: NewExistingArgs) -> Result<(), ProjUpError>
     }
     
     // write out new backups
-    fs::write(&file, b.to_content()).projup(&file)?;
+    ops::write_registry(&file, b.to_content()).projup(&file)?;
     info!("Successfully opened project {}", &location);
     return Ok(());
 }
\ No newline at end of file
@@ -1,6 +1,5 @@
-use std::fs;
 use log::info;
-use projup::{error::{HandleProjUpError, IntoProjUpError, ProjUpError}, file};
+use projup::{error::{HandleProjUpError, IntoProjUpError, ProjUpError}, file::{self, ops}};
 use crate::cli::RemoveArgs;
 use super::load_backups;
 
@@ -19,10 +18,10 @@ pub fn remove(args: RemoveArgs) -> Result<(), ProjUpError>
             return Err(ProjUpError::BackupUnavailable(b.into_location()));
         }
         
-        fs::remove_dir_all(&path.0).projup(path.0).handle();
+        ops::remove_dir_all(&path.0).projup(path.0).handle();
     }
     
-    fs::write(&file, b.to_content()).projup(&file)?;
+    ops::write_registry(&file, b.to_content()).projup(&file)?;
     info!("{} removed from registry", &args.name);
     return Ok(());
 }
\ No newline at end of file
@@ -0,0 +1,163 @@
+use std::{cmp::Ordering, path::{Path, PathBuf}};
+use log::{info, warn};
+use projup::{data::{template_config, TemplateSource, Templates, VersionReq}, duplicate_template, error::{HandleProjUpError, IntoProjUpError, ProjUpError}, file::{self, ops}, path_exists};
+use crate::{cli::{TemplateAddArgs, TemplateUpdateArgs}, git};
+use super::{load_templates, Step, Transaction};
+
+pub(crate) fn add_template(args: TemplateAddArgs) -> Result<(), ProjUpError>
+{
+    let file = file::get_template_path()?;
+    let mut t = load_templates(&file)?;
+    
+    // local repositories are tracked by their full path
+    let source = Path::new(&args.source);
+    let url = if source.exists()
+    {
+        let full = file::absolute(source).projup(source)?;
+        full.to_str().ok_or(ProjUpError::UtfString)?.to_string()
+    }
+    else
+    {
+        args.source.clone()
+    };
+    
+    let mut transaction = Transaction::new();
+    let result = clone_template(&mut t, url, args.reference, &mut transaction);
+    if result.is_err()
+    {
+        transaction.rollback();
+    }
+    result?;
+    
+    ops::write_registry(&file, t.to_content()).projup(&file)?;
+    return Ok(());
+}
+
+fn clone_template(t: &mut Templates, url: String, reference: Option<String>, transaction: &mut Transaction) -> Result<(), ProjUpError>
+{
+    // clone into the name of the repository, then rename to the name in .projup
+    let repo = url.trim_end_matches(['/', '\\']);
+    let repo = repo.rsplit(['/', '\\', ':']).next().unwrap_or(repo);
+    let repo = repo.strip_suffix(".git").unwrap_or(repo);
+    let path = PathBuf::from_iter([t.get_location().as_str(), repo]);
+    if path.exists()
+    {
+        return path_exists!(path);
+    }
+    
+    git::run(git::GitOperation::Clone {
+        url: Path::new(&url),
+        path: Some(&path),
+        reference: reference.as_deref(),
+        depth: None
+    }, t.get_location())?;
+    transaction.push(Step::CreatedDir(path.clone()));
+    
+    // nothing was cloned to read
+    if ops::is_dry_run()
+    {
+        return Ok(());
+    }
+    
+    let config = template_config(&path)?;
+    if t.try_get_version(&config.name, &VersionReq::exact(config.version.clone()))?.is_some()
+    {
+        return duplicate_template!(format!("{}@{}", config.name, config.version));
+    }
+    let np = t.template_folder(Some(repo), &config)?;
+    if np != path
+    {
+        ops::rename(&path, &np).projup(&path)?;
+    }
+    
+    info!("Added template \"{}\" at version {}", config.name, config.version);
+    // will be valid utf8 as it is made from the config
+    t.add_source(np.file_name().unwrap_or_default().to_string_lossy().to_string(), TemplateSource { url, reference });
+    return Ok(());
+}
+
+pub(crate) fn update_templates(args: TemplateUpdateArgs) -> Result<(), ProjUpError>
+{
+    let file = file::get_template_path()?;
+    let mut t = load_templates(&file)?;
+    
+    let names: Vec<String> = match args.name
+    {
+        Some(n) =>
+        {
+            if t.try_get_source(&n).is_none()
+            {
+                return Err(ProjUpError::UntrackedTemplate(n));
+            }
+            vec![n]
+        },
+        None => t.sources().map(|(n, _)| n.clone()).collect()
+    };
+    
+    for n in names
+    {
+        // other templates can still be updated
+        update_template(&mut t, &n).handle();
+    }
+    
+    ops::write_registry(&file, t.to_content()).projup(&file)?;
+    return Ok(());
+}
+
+fn update_template(t: &mut Templates, name: &str) -> Result<(), ProjUpError>
+{
+    // sources are always in the map
+    let path = t.try_get_template(name).unwrap();
+    let source = t.try_get_source(name).unwrap().clone();
+    
+    let old = template_config(&path)?;
+    let old_head = git::head(&path)?;
+    
+    match &source.reference
+    {
+        Some(r) =>
+        {
+            git::run(git::GitOperation::Fetch { remote: "origin", reference: r, depth: None }, &path)?;
+            git::run(git::GitOperation::Checkout { reference: "FETCH_HEAD" }, &path)?;
+        },
+        None => git::run(git::GitOperation::Pull, &path)?
+    }
+    
+    // nothing was pulled to compare
+    if ops::is_dry_run()
+    {
+        return Ok(());
+    }
+    
+    let new = template_config(&path)?;
+    let new_head = git::head(&path)?;
+    
+    // the name or version in the folder name could have changed
+    let np = t.template_folder(Some(name), &new)?;
+    if np != path
+    {
+        if t.try_get_version(&new.name, &VersionReq::exact(new.version.clone()))?.is_some_and(|(p, _)| p != path)
+        {
+            return duplicate_template!(format!("{}@{}", new.name, new.version));
+        }
+        ops::rename(&path, &np).projup(&path)?;
+        t.try_remove(name);
+        // will be valid utf8 as it is made from the config
+        let folder = np.file_name().unwrap_or_default().to_string_lossy().to_string();
+        info!("Template \"{}\" was moved to \"{}\"", name, folder);
+        t.add_source(folder, source);
+    }
+    
+    match new.version.cmp_precedence(&old.version)
+    {
+        Ordering::Greater => info!("Upgraded \"{}\" from version {} to {}", new.name, old.version, new.version),
+        Ordering::Less => warn!("Template \"{}\" went from version {} down to {}", new.name, old.version, new.version),
+        Ordering::Equal if new_head != old_head =>
+        {
+            info!("Updated \"{}\" to the latest commit, still at version {}", new.name, new.version);
+        },
+        Ordering::Equal => info!("Template \"{}\" is already up to date at version {}", new.name, new.version)
+    }
+    
+    return Ok(());
+}
\ No newline at end of file
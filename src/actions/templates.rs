@@ -1,10 +1,25 @@
-use std::{collections::{HashMap, HashSet}, fs, path::{Path, PathBuf}};
-use log::info;
-use projup::{data::{Config, ConfigArgs, VariableMap}, error::{HandleProjUpError, IntoProjUpError, ProjUpError}, file::{self, traverse, ParserData}, invalid_config, missing_projup, VAR_DATE, VAR_NAME, VAR_TIME};
+use std::{collections::{HashMap, HashSet}, ffi::{OsStr, OsString}, fs::{self, File}, io::{self, BufReader, BufWriter, IsTerminal, Write}, path::{Path, PathBuf}, sync::Mutex};
+use log::{info, warn};
+use projup::{data::{split_template_name, template_config, Config, ConfigArgs, ConfigError, Dependency, DependencyKind, Hook, VariableMap}, error::{HandleProjUpError, IntoProjUpError, ProjUpError}, file::{self, ops, traverse, ParserData, TemplateFilter}, invalid_config, invalid_template_file, missing_projup, VAR_DATE, VAR_NAME, VAR_TIME};
 
-use crate::{cli::TemplateArgs, git};
+use crate::{cli::{Conflict, TemplateArgs, TemplateCommand}, git};
 
-use super::load_templates;
+use super::{add_template, load_templates, update_templates};
+
+/// Files larger than this many bytes are streamed rather than read into memory
+const STREAM_THRESHOLD: u64 = 1024 * 1024;
+
+/// How a template file is written into the project
+enum Content
+{
+    /// target of a symbolic link
+    Link(PathBuf),
+    /// copied byte for byte
+    Binary,
+    /// substituted without reading the whole file
+    Stream,
+    Text(String)
+}
 
 struct VarCounter
 {
@@ -17,9 +32,9 @@ impl VarCounter
         return Self { set: HashMap::new() };
     }
 }
-impl VariableMap for &mut VarCounter
+impl VariableMap for VarCounter
 {
-    fn map(&mut self, _i: usize, v: &str, f: Option<String>) -> Result<String, projup::data::ConfigError>
+    fn map(&mut self, _i: usize, v: &str, f: Option<String>) -> Result<String, ConfigError>
     {
         match self.set.get_mut(v)
         {
@@ -47,25 +62,21 @@ impl VariableMap for &mut VarCounter
 
 pub fn templates(args: TemplateArgs) -> Result<(), ProjUpError>
 {
+    match args.command
+    {
+        Some(TemplateCommand::Add(a)) => return add_template(a),
+        Some(TemplateCommand::Update(u)) => return update_templates(u),
+        None => {}
+    }
+    
     if let Some(q) = args.query
     {
-        let mut path = find_template(&q)?;
-        path.push(".projup");
-        if !path.exists()
-        {
-            return missing_projup!(path);
-        }
+        let path = find_template(&q)?;
         
         let mut variables = VarCounter::new();
+        let (config, _) = load_config_chain(&path, &mut variables)?;
         
-        let content = fs::read_to_string(&path).projup(&path)?;
-        match Config::from_content(content.as_str(), Some(&mut variables))
-        {
-            Ok(_) => {},
-            Err(e) => return invalid_config!(path, e)
-        };
-        
-        for (name, formats) in variables.set
+        for (name, formats) in &variables.set
         {
             let v_type = if name == VAR_NAME || name == VAR_DATE || name == VAR_TIME
             {
@@ -80,6 +91,31 @@ pub fn templates(args: TemplateArgs) -> Result<(), ProjUpError>
                 info!("{v_type} \"{}\" expected with formats: {:?}", name, formats);
             }
         }
+        for v in &config.vars
+        {
+            let used = if variables.set.contains_key(&v.name) { "" } else { " (unused)" };
+            info!("Declared variable \"{}\"{used}", v.name);
+            if let Some(d) = &v.description
+            {
+                info!("    {}", d);
+            }
+            if let Some(d) = v.default_to_string()
+            {
+                info!("    default: \"{}\"", d);
+            }
+            if !v.values.is_empty()
+            {
+                info!("    allowed values: {:?}", v.values);
+            }
+        }
+        for f in config.files
+        {
+            info!("Files matching \"{}\" controlled by variable \"{}\"", f.pattern, f.variable);
+        }
+        for h in config.hooks
+        {
+            info!("Hook \"{}\" run after creating a project, on error: {}", h.command, h.on_error);
+        }
         
         return Ok(());
     }
@@ -89,94 +125,466 @@ pub fn templates(args: TemplateArgs) -> Result<(), ProjUpError>
     let mut t = load_templates(&file)?;
     t.find_templates(args.list).handle();
     
-    fs::write(&file, t.to_content()).projup(&file)?;
+    ops::write_registry(&file, t.to_content()).projup(&file)?;
     return Ok(());
 }
 
+/// Finds the template from `name@requirement`, or the highest version of the template if there is no requirement
 pub(crate) fn find_template(name: &str) -> Result<PathBuf, ProjUpError>
 {
     let file = file::get_template_path()?;
     
     let mut t = load_templates(&file)?;
     
-    match t.try_get_template(name)
+    // registry could be out of date
+    if let Ok(path) = t.find(name)
+    {
+        return Ok(path);
+    }
+    
+    t.find_templates(false).map_err(|_| ProjUpError::UnkownTemplate(split_template_name(name).0.to_string()))?;
+    let path = t.find(name)?;
+    // ignore errors here
+    let _ = ops::write_registry(&file, t.to_content());
+    return Ok(path);
+}
+
+/// Asks for the value of each variable used by `template` that is not in `args`,
+/// only if stdin is a terminal
+pub(crate) fn prompt_missing_variables(template: &Path, args: &mut Vec<(String, String)>, name: &str) -> Result<(), ProjUpError>
+{
+    if !io::stdin().is_terminal()
+    {
+        return Ok(());
+    }
+    
+    let mut variables = VarCounter::new();
+    let (config, _) = load_config_chain(template, &mut variables)?;
+    prompt_variables(&config, variables.set.into_keys().collect(), args)?;
+    
+    // the conditions of [files] are known now, so that only the files that are copied are read
+    let mut variables = ConfigArgs::new(name);
+    for v in args.iter()
     {
-        Some(path) => return Ok(path),
-        None =>
+        variables.map.insert(&v.0, &v.1);
+    }
+    let (config, templates) = load_config_chain(template, &mut variables)?;
+    let mut used = Vec::new();
+    for t in &templates
+    {
+        let filter = TemplateFilter::new(t, &config)?;
+        file::file_variables(t, t, &filter, &mut used)?;
+    }
+    return prompt_variables(&config, used, args);
+}
+
+/// Asks for the value of each of `used` that is not in `args`,
+/// along with the variables only referenced by their defaults
+fn prompt_variables(config: &Config, mut used: Vec<String>, args: &mut Vec<(String, String)>) -> Result<(), ProjUpError>
+{
+    let mut index = 0;
+    while index < used.len()
+    {
+        if let Some(d) = config.vars.iter().find(|v| v.name == used[index])
         {
-            t.find_templates(false).map_err(|_| ProjUpError::UnkownTemplate(name.to_string()))?;
-            let path = t.try_get_template(name).ok_or(ProjUpError::UnkownTemplate(name.to_string()))?;
-            // ignore errors here
-            let _ = fs::write(&file, t.to_content());
-            return Ok(path);
-        },
-    };
+            for r in d.references()
+            {
+                if !used.iter().any(|u| u == r)
+                {
+                    used.push(r.to_string());
+                }
+            }
+        }
+        index += 1;
+    }
+    
+    let mut missing: Vec<String> = used.into_iter()
+        .filter(|n| n != VAR_NAME && n != VAR_DATE && n != VAR_TIME)
+        .filter(|n| !args.iter().any(|(k, _)| k == n))
+        .collect();
+    missing.sort();
+    
+    for name in missing
+    {
+        let decl = config.vars.iter().find(|v| v.name == name);
+        if let Some(d) = decl.and_then(|v| v.description.as_ref())
+        {
+            println!("{}", d);
+        }
+        
+        // ask again until the value is valid
+        loop
+        {
+            match decl.and_then(|v| v.default_to_string())
+            {
+                Some(d) => print!("Value for \"{}\" [{}]: ", name, d),
+                None => print!("Value for \"{}\": ", name)
+            }
+            io::stdout().flush().projup("")?;
+            
+            let mut value = String::new();
+            io::stdin().read_line(&mut value).projup("")?;
+            let value = value.trim_end_matches(['\n', '\r']).to_string();
+            
+            let decl = match decl
+            {
+                Some(d) => d,
+                None =>
+                {
+                    args.push((name, value));
+                    break;
+                }
+            };
+            // keep the default
+            if value.is_empty() && decl.default.is_some()
+            {
+                break;
+            }
+            if let Err(e) = decl.validate(&value)
+            {
+                println!("{}", e);
+                continue;
+            }
+            
+            args.push((name, value));
+            break;
+        }
+    }
+    
+    return Ok(());
 }
 
-pub(crate) fn load_template_to_source(template: impl AsRef<Path>, source: impl AsRef<Path>,
-    args: &[(String, String)], name: &str) -> Result<(), ProjUpError>
+/// Checks that the config of `template` loads with the given variables,
+/// so that invalid values are found before anything is created
+pub(crate) fn check_variables(template: &Path, args: &[(String, String)], name: &str) -> Result<(), ProjUpError>
 {
-    // construct variables from args
     let mut variables = ConfigArgs::new(name);
     for v in args
     {
         variables.map.insert(&v.0, &v.1);
     }
     
-    let p = template.as_ref().join(".projup");
-    if !p.exists()
+    load_config_chain(template, &mut variables)?;
+    return Ok(());
+}
+
+/// Loads the config of `template` layered on top of the templates it extends,
+/// also returning the template directories starting with the furthest parent
+pub(crate) fn load_config_chain<T>(template: &Path, args: &mut T) -> Result<(Config, Vec<PathBuf>), ProjUpError>
+    where T: VariableMap
+{
+    let mut chain: Vec<(PathBuf, Config)> = Vec::new();
+    let mut path = template.to_path_buf();
+    
+    loop
     {
-        return missing_projup!(p);
+        let p = path.join(".projup");
+        if !p.exists()
+        {
+            return missing_projup!(p);
+        }
+        let content = fs::read_to_string(&p).projup(&p)?;
+        let config = match Config::from_content(content.as_str(), Some(&mut *args))
+        {
+            Ok(c) => c,
+            Err(e) => return invalid_config!(p, e)
+        };
+        
+        let parent = config.extends.clone();
+        chain.push((path, config));
+        
+        let parent = match parent
+        {
+            Some(n) => n,
+            None => break
+        };
+        path = find_template(&parent)?;
+        // other versions of the same template can be extended
+        let resolved = template_config(&path)?;
+        if chain.iter().any(|(_, c)| c.name == resolved.name && c.version.cmp_precedence(&resolved.version).is_eq())
+        {
+            return invalid_config!(p, ConfigError::CyclicExtends(parent));
+        }
     }
-    let content = fs::read_to_string(&p).projup(&p)?;
-    // load template config file with user given variables
-    let mut config = match Config::from_content(content.as_str(), Some(variables))
+    
+    let mut it = chain.into_iter();
+    // chain will have at least one config
+    let (first, mut config) = it.next().unwrap();
+    let mut paths = vec![first];
+    // closer parents take priority
+    for (p, c) in it
     {
-        Ok(c) => c,
-        Err(e) => return invalid_config!(p, e)
-    };
-    // keys need to be sorted
-    config.keys.sort_by(|a, b| a.0.cmp(&b.0));
-    let parse_data = ParserData::new(&config.keys);
+        config.inherit(c);
+        paths.push(p);
+    }
+    paths.reverse();
+    
+    return Ok((config, paths));
+}
+
+/// Returns the hooks of the template, along with the subtree dependencies that were added,
+/// which are only copies until `commit_project` merges them
+pub(crate) fn load_template_to_source(template: impl AsRef<Path>, source: impl AsRef<Path>,
+    args: &[(String, String)], name: &str, conflict: Conflict) -> Result<(Vec<Hook>, Vec<Dependency>), ProjUpError>
+{
+    // construct variables from args
+    let mut variables = ConfigArgs::new(name);
+    for v in args
+    {
+        variables.map.insert(&v.0, &v.1);
+    }
     
-    traverse::copy_dir_all_func(&template, &source, &|from, mut to|
+    // load template config files with user given variables
+    let (config, templates) = load_config_chain(template.as_ref(), &mut variables)?;
+    let parse_data = ParserData::new(&config.keys);
+    // files written by this call, which are not conflicts when layering templates
+    let written = Mutex::new(HashSet::new());
+    // so that conflicts from multiple layered templates are only reported once
+    let conflicts = Mutex::new(HashSet::new());
+    // file and directory names are substituted in the same way
+    let render_name = |name: &OsStr| -> OsString
     {
-        if from == p
+        if !config.file_names
         {
-            return Ok(());
+            return name.to_os_string();
         }
+        // parse name and change if can
+        return match name.to_str().map(|s| String::from_utf8(file::parse(s, &parse_data)))
+        {
+            Some(Ok(nn)) => nn.into(),
+            _ => name.to_os_string()
+        };
+    };
+    
+    // parent templates are copied first so that their files are overridden
+    for template in templates
+    {
+        let filter = TemplateFilter::new(&template, &config)?;
+        // the template file each path was rendered from, as only files of parent templates can be replaced
+        let rendered = traverse::RenderedPaths::default();
         
-        let content = fs::read_to_string(&from).projup(&from)?;
-        let data = file::parse(&content, &parse_data);
-        
-        // do file names as well?
-        if config.file_names
+        traverse::copy_dir_rename_func(&template, &source, &|rel, is_dir| filter.is_included(rel, is_dir), &render_name, &|from, to|
         {
-            // parse file name and change if can
-            if let Some(str) = to.file_name().and_then(|os| os.to_str())
+            // files named with a loop are written once per element
+            let name = to.file_name().and_then(|os| os.to_str()).unwrap_or_default();
+            let outputs = match file::split_name_loop(name)
+            {
+                Some(Ok((l, rest))) =>
+                {
+                    let items = match l.items(0, &mut variables.clone())
+                    {
+                        Ok(i) => i,
+                        Err(e) => return invalid_template_file!(from, e)
+                    };
+                    items.into_iter().enumerate()
+                        .map(|(index, item)|
+                        {
+                            let scope = l.scope(index, item).to_vec();
+                            (OsString::from(file::replace_loop_refs(rest, &scope).as_ref()), scope)
+                        })
+                        .collect()
+                },
+                Some(Err(_)) => return invalid_template_file!(from, ConfigError::InvalidFileLoop(name.to_string())),
+                None => vec![(to.file_name().unwrap_or_default().to_os_string(), Vec::new())]
+            };
+            
+            // from will always be within template
+            let rel = from.strip_prefix(&template).unwrap_or(&from);
+            let meta = fs::symlink_metadata(&from).projup(&from)?;
+            
+            let content = if meta.is_symlink()
+            {
+                Content::Link(fs::read_link(&from).projup(&from)?)
+            }
+            else if filter.is_binary(rel)
+            {
+                Content::Binary
+            }
+            // large files are streamed, unless they have blocks which need the whole file
+            else if meta.len() > STREAM_THRESHOLD
+            {
+                let info = file::inspect(File::open(&from).projup(&from)?).projup(&from)?;
+                if info.binary
+                {
+                    Content::Binary
+                }
+                else if info.blocks
+                {
+                    Content::Text(fs::read_to_string(&from).projup(&from)?)
+                }
+                else { Content::Stream }
+            }
+            else
+            {
+                let content = fs::read(&from).projup(&from)?;
+                match file::is_binary(&content)
+                {
+                    true => Content::Binary,
+                    // is_binary checks that it is utf8
+                    false => Content::Text(String::from_utf8(content).unwrap())
+                }
+            };
+            
+            for (name, items) in outputs
             {
-                let new_name = file::parse(str, &parse_data);
-                if let Ok(nn) = std::str::from_utf8(&new_name)
+                let name = render_name(&name);
+                traverse::check_name(&from, &name)?;
+                let mut to = to.with_file_name(name);
+                rendered.insert(&from, &to)?;
+                
+                if to.exists() && !written.lock().unwrap().contains(&to)
+                {
+                    let report = conflicts.lock().unwrap().insert(to.clone());
+                    match conflict
+                    {
+                        Conflict::Skip =>
+                        {
+                            if report
+                            {
+                                warn!("Skipped existing file {}", to.display());
+                            }
+                            continue;
+                        },
+                        Conflict::Overwrite => {},
+                        Conflict::New =>
+                        {
+                            // new files of parent templates are replaced
+                            let written = written.lock().unwrap();
+                            to = traverse::new_path(&to, |p| written.contains(p));
+                            if report
+                            {
+                                warn!("Wrote {} next to existing file", to.display());
+                            }
+                        }
+                    }
+                }
+                
+                let result = match &content
+                {
+                    Content::Link(target) =>
+                    {
+                        // targets are substituted in the same way as file names
+                        let mut target = file::replace_loop_refs(&target.to_string_lossy(), &items).into_owned();
+                        if config.file_names
+                        {
+                            if let Ok(t) = String::from_utf8(file::parse(&target, &parse_data))
+                            {
+                                target = t;
+                            }
+                        }
+                        let dir = fs::metadata(&from).is_ok_and(|m| m.is_dir());
+                        ops::symlink(target, &to, dir)
+                    },
+                    Content::Binary => ops::copy(&from, &to),
+                    Content::Stream =>
+                    {
+                        ops::write_with(&to, |f|
+                        {
+                            let reader = BufReader::new(File::open(&from)?);
+                            return file::parse_stream(reader, BufWriter::new(f), &parse_data, file::STREAM_CAPACITY);
+                        })
+                    },
+                    Content::Text(text) =>
+                    {
+                        // blocks are evaluated with a copy of the config variables, as files are rendered on multiple threads
+                        let text = match file::parse_blocks_scoped(text, &mut variables.clone(), items)
+                        {
+                            Ok(c) => c,
+                            Err(e) => return invalid_template_file!(from, e)
+                        };
+                        ops::write(&to, file::parse(&text, &parse_data))
+                    }
+                };
+                result.projup(&to)?;
+                
+                // links do not have their own permissions
+                if !meta.is_symlink()
                 {
-                    to.pop();
-                    to.push(nn);
+                    ops::copy_metadata(&meta, &to, config.preserve_times).projup(&to)?;
                 }
+                written.lock().unwrap().insert(to);
             }
+            
+            return Ok(());
+        })?;
+    }
+    
+    // load dependencies
+    // path validity already checked by config parser
+    let mut subtrees = Vec::new();
+    for d in config.deps
+    {
+        if conflict != Conflict::Overwrite && source.as_ref().join(&d.path).exists()
+        {
+            warn!("Skipped existing dependency path {}", d.path);
+            continue;
         }
         
-        fs::write(&to, data).projup(&to)?;
-        return Ok(());
-    })?;
+        add_dependency(&d, source.as_ref())?;
+        if d.kind == DependencyKind::Subtree
+        {
+            subtrees.push(d);
+        }
+    }
     
-    // load submodules
-    // path validity already checked by config parser
-    for (path, url) in config.deps
+    return Ok((config.hooks, subtrees));
+}
+
+fn add_dependency(d: &Dependency, source: &Path) -> Result<(), ProjUpError>
+{
+    let path = Path::new(&d.path);
+    match d.kind
     {
-        git::run(git::GitOperation::SubmoduleAdd {
-                url: &url,
-                path: path.as_ref()
-            }, &source)?;
+        DependencyKind::Submodule =>
+        {
+            git::run(git::GitOperation::SubmoduleAdd {
+                    url: &d.url,
+                    path,
+                    branch: d.branch.as_deref(),
+                    depth: d.depth,
+                    name: d.name.as_deref()
+                }, source)?;
+        },
+        DependencyKind::Clone =>
+        {
+            git::run(git::GitOperation::Clone {
+                    url: Path::new(&d.url),
+                    path: Some(path),
+                    reference: d.branch.as_deref(),
+                    depth: d.depth
+                }, source)?;
+            if let Some(c) = &d.commit
+            {
+                git::run(git::GitOperation::Checkout { reference: c }, source.join(path))?;
+            }
+        },
+        DependencyKind::Subtree | DependencyKind::Vendor =>
+        {
+            fetch_dependency(d, source)?;
+            // a subtree merge needs a commit to merge into, so the files are copied until the project is committed
+            git::run(git::GitOperation::ReadTree { prefix: path, reference: "FETCH_HEAD" }, source)?;
+            if d.kind == DependencyKind::Vendor
+            {
+                git::run(git::GitOperation::Unstage { path }, source)?;
+            }
+        }
     }
     
     return Ok(());
+}
+
+/// Fetches the commit or branch of `d` into FETCH_HEAD
+fn fetch_dependency(d: &Dependency, source: &Path) -> Result<(), ProjUpError>
+{
+    // the commit takes priority, as it can be on the branch
+    let reference = d.commit.as_deref().or(d.branch.as_deref()).unwrap_or("HEAD");
+    return git::run(git::GitOperation::Fetch { remote: &d.url, reference, depth: d.depth }, source);
+}
+
+/// Adds the subtree dependency `d` with a subtree merge, which needs the project to have a commit
+/// and the folder of the dependency to not exist
+pub(crate) fn add_subtree(d: &Dependency, source: &Path) -> Result<(), ProjUpError>
+{
+    fetch_dependency(d, source)?;
+    return git::run(git::GitOperation::SubtreeAdd { prefix: Path::new(&d.path), reference: "FETCH_HEAD" }, source);
 }
\ No newline at end of file
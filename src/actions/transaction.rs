@@ -0,0 +1,113 @@
+use std::path::{Path, PathBuf};
+use log::info;
+use projup::{error::{HandleProjUpError, IntoProjUpError, ProjUpError}, file::{self, ops}};
+use super::load_backups;
+
+/// A completed step that can be undone
+pub(crate) enum Step
+{
+    CreatedDir(PathBuf),
+    Registered(String),
+    /// 0 was moved to 1 so that it could be replaced, and 1 is deleted once the transaction is finished
+    Replaced(PathBuf, PathBuf)
+}
+
+/// Records the steps of a command so that they can be undone if a later step fails
+pub(crate) struct Transaction
+{
+    steps: Vec<Step>
+}
+
+impl Transaction
+{
+    pub fn new() -> Self
+    {
+        return Self { steps: Vec::new() };
+    }
+    
+    pub fn push(&mut self, step: Step)
+    {
+        self.steps.push(step);
+    }
+    
+    /// Undoes all steps in reverse order, logging any errors
+    pub fn rollback(self)
+    {
+        // nothing was changed to undo
+        if ops::is_dry_run()
+        {
+            return;
+        }
+        
+        for step in self.steps.into_iter().rev()
+        {
+            match step
+            {
+                Step::CreatedDir(path) =>
+                {
+                    if ops::remove_dir_all(&path).projup(&path).handle()
+                    {
+                        info!("Removed {}", path.display());
+                    }
+                },
+                Step::Registered(name) =>
+                {
+                    if unregister(&name).handle()
+                    {
+                        info!("Removed \"{}\" from registry", name);
+                    }
+                },
+                Step::Replaced(path, aside) =>
+                {
+                    if ops::rename(&aside, &path).projup(&aside).handle()
+                    {
+                        info!("Restored {}", path.display());
+                    }
+                }
+            }
+        }
+    }
+    
+    /// Deletes the folders that were replaced, as the steps no longer need to be undone
+    pub fn finish(self)
+    {
+        for step in self.steps
+        {
+            if let Step::Replaced(_, aside) = step
+            {
+                ops::remove_dir_all(&aside).projup(&aside).handle();
+            }
+        }
+    }
+}
+
+/// Returns the outermost folder of `path` that does not exist, which is the folder to remove to undo `create_dir_all`
+pub(crate) fn first_missing(path: &Path) -> PathBuf
+{
+    let mut missing = path;
+    // relative paths end in an empty path, which never exists
+    for p in path.ancestors().skip(1).take_while(|p| !p.as_os_str().is_empty())
+    {
+        if p.exists()
+        {
+            break;
+        }
+        missing = p;
+    }
+    
+    return missing.to_path_buf();
+}
+
+fn unregister(name: &str) -> Result<(), ProjUpError>
+{
+    let file = file::get_projects_path()?;
+    let mut b = load_backups(&file)?;
+    
+    // never written
+    if b.try_remove(name).is_none()
+    {
+        return Ok(());
+    }
+    ops::write_registry(&file, b.to_content()).projup(&file)?;
+    return Ok(());
+}
\ No newline at end of file
@@ -1,15 +1,27 @@
 use std::{error::Error, path::PathBuf};
 
-use clap::{Args, Parser};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[command(about, long_about = None, disable_version_flag = true)]
-pub enum Cli
+pub struct Cli
+{
+    /// Prints the operations that would be performed without performing them
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+    #[command(subcommand)]
+    pub command: Command
+}
+
+#[derive(Subcommand)]
+pub enum Command
 {
     /// Creates a new project, loading optional templates and adding it to the backup registry
     New(NewArgs),
     /// Adds an existing project to the backup registry
     NewExisting(NewExistingArgs),
+    /// Loads a template into a project that is included in the registry
+    Apply(ApplyArgs),
     /// Moves or renames a project that is included in the registry
     Move(MoveArgs),
     /// Removes a project from the registry
@@ -37,6 +49,16 @@ pub struct NewArgs
     /// Specifics that any conflicting folder in the backup location should be replaced
     #[arg(short, long)]
     pub force: bool,
+    /// Specifies that a partially created project should not be removed if an error occurs
+    #[arg(long)]
+    pub keep_on_error: bool,
+    /// Specifies that the hooks of the template should not be run, for templates that are not trusted
+    #[arg(long)]
+    pub no_hooks: bool,
+    /// Stages all files and creates a commit, which is pushed to the backup.
+    /// An optional message can be given, which can reference variables
+    #[arg(long, value_name = "MESSAGE", num_args = 0..=1, require_equals = true, default_missing_value = "Initial commit of $name")]
+    pub commit: Option<String>,
     
     /// Extra varaibles to pass to the template
     #[arg(short = 'D', number_of_values = 1, value_parser = parse_key_val::<String, String>)]
@@ -56,6 +78,39 @@ pub struct NewExistingArgs
     pub force: bool
 }
 
+#[derive(Args)]
+pub struct ApplyArgs
+{
+    /// The name of the project entry to load the template into
+    pub name: String,
+    /// The template to load into the project directory
+    #[arg(short, long)]
+    pub template: String,
+    /// Specifies what to do with template files that already exist in the project
+    #[arg(short, long, value_enum, default_value_t = Conflict::Skip)]
+    pub conflict: Conflict,
+    /// Stages all files and creates a commit, which is pushed to the backup.
+    /// An optional message can be given, which can reference variables
+    #[arg(long, value_name = "MESSAGE", num_args = 0..=1, require_equals = true, default_missing_value = "Apply template $template")]
+    pub commit: Option<String>,
+    
+    /// Extra varaibles to pass to the template
+    #[arg(short = 'D', number_of_values = 1, value_parser = parse_key_val::<String, String>)]
+    pub variables: Vec<(String, String)>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Conflict
+{
+    /// Keep the existing file
+    Skip,
+    /// Replace the existing file
+    Overwrite,
+    /// Write the template file next to the existing file with a ".new" extension,
+    /// followed by a number if that file exists too
+    New
+}
+
 #[derive(Args)]
 pub struct MoveArgs
 {
@@ -94,7 +149,35 @@ pub struct TemplateArgs
     pub list: bool,
     /// Specifics a specific template to query for errors and variables
     #[arg(short, long)]
-    pub query: Option<String>
+    pub query: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<TemplateCommand>
+}
+
+#[derive(Subcommand)]
+pub enum TemplateCommand
+{
+    /// Clones a template from a git repository into the template location
+    Add(TemplateAddArgs),
+    /// Pulls newer commits of templates that were added from git repositories
+    Update(TemplateUpdateArgs)
+}
+
+#[derive(Args)]
+pub struct TemplateAddArgs
+{
+    /// The url or path of the git repository
+    pub source: String,
+    /// The tag or branch to check out, instead of the default branch
+    #[arg(short, long = "ref")]
+    pub reference: Option<String>
+}
+
+#[derive(Args)]
+pub struct TemplateUpdateArgs
+{
+    /// The template to update, all templates added from git repositories are updated if not given
+    pub name: Option<String>
 }
 
 #[derive(Args)]
@@ -1,8 +1,15 @@
-use std::str::FromStr;
+use std::{fmt::Display, str::FromStr};
 use thiserror::Error;
 
-use crate::{file::{Object, Token}, VAR_FILE_NAMES, VAR_NAME, VAR_VERSION};
-use super::{VariableMap, Version};
+use crate::{file::{Condition, Object, Token}, VAR_EXTENDS, VAR_FILE_NAMES, VAR_NAME, VAR_PRESERVE_TIMES, VAR_VERSION};
+use super::{VarDecl, VarType, VariableMap, Version};
+
+pub const ATTR_ON_ERROR: &str = "@on_error";
+pub const ATTR_BRANCH: &str = "@branch";
+pub const ATTR_DEPTH: &str = "@depth";
+pub const ATTR_NAME: &str = "@name";
+pub const ATTR_KIND: &str = "@kind";
+pub const ATTR_COMMIT: &str = "@commit";
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Config
@@ -10,9 +17,180 @@ pub struct Config
     pub name: String,
     pub version: Version,
     pub file_names: bool,
+    /// whether files keep the modification times of the template files
+    pub preserve_times: bool,
+    /// name of the template this template is layered on top of
+    pub extends: Option<String>,
     pub keys: Vec<(String, String)>,
-    /// 0 is relative path, 1 is url
-    pub deps: Vec<(String, String)>
+    pub deps: Vec<Dependency>,
+    pub files: Vec<FileFilter>,
+    /// glob patterns of files that are copied without substitution
+    pub binary: Vec<String>,
+    pub vars: Vec<VarDecl>,
+    /// commands run in the project directory after the template is loaded
+    pub hooks: Vec<Hook>
+}
+
+/// A glob pattern of template files that are only included if the condition on `variable` is met
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileFilter
+{
+    pub pattern: String,
+    pub variable: String,
+    pub include: bool
+}
+
+/// A git repository from the `[deps]` section of a .projup file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency
+{
+    /// relative to the project directory
+    pub path: String,
+    pub url: String,
+    pub kind: DependencyKind,
+    /// the branch or tag to check out instead of the default branch
+    pub branch: Option<String>,
+    /// the commit to check out, which cannot be used for submodules
+    pub commit: Option<String>,
+    /// the number of commits to fetch, for a shallow clone
+    pub depth: Option<usize>,
+    /// the name of the submodule, which is the path if not given
+    pub name: Option<String>
+}
+
+impl Dependency
+{
+    pub fn new(path: String, url: String) -> Self
+    {
+        return Self { path, url, kind: DependencyKind::Submodule, branch: None, commit: None, depth: None, name: None };
+    }
+    
+    /// Returns an attribute that has been set but cannot be used with the kind of dependency
+    fn invalid_attribute(&self) -> Option<&'static str>
+    {
+        if self.name.is_some() && self.kind != DependencyKind::Submodule
+        {
+            return Some(ATTR_NAME);
+        }
+        if self.commit.is_some() && self.kind == DependencyKind::Submodule
+        {
+            return Some(ATTR_COMMIT);
+        }
+        return None;
+    }
+}
+
+/// Whether `value` is a full sha1 or sha256 commit hash
+fn is_commit_hash(value: &str) -> bool
+{
+    return (value.len() == 40 || value.len() == 64) && value.chars().all(|c| c.is_ascii_hexdigit());
+}
+
+/// How a dependency is added to the project
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DependencyKind
+{
+    /// a git submodule
+    #[default]
+    Submodule,
+    /// a separate repository inside the project
+    Clone,
+    /// a squashed subtree merge once the project is committed, without the history of the dependency
+    Subtree,
+    /// files copied into the project without being staged
+    Vendor
+}
+
+impl FromStr for DependencyKind
+{
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        return match s
+        {
+            "submodule" => Ok(Self::Submodule),
+            "clone" => Ok(Self::Clone),
+            "subtree" => Ok(Self::Subtree),
+            "vendor" => Ok(Self::Vendor),
+            _ => Err(s.to_string())
+        };
+    }
+}
+impl Display for DependencyKind
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        return f.write_str(match self
+        {
+            Self::Submodule => "submodule",
+            Self::Clone => "clone",
+            Self::Subtree => "subtree",
+            Self::Vendor => "vendor"
+        });
+    }
+}
+
+/// A command from the `[hooks]` section of a .projup file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hook
+{
+    pub command: String,
+    pub on_error: HookFailure
+}
+
+/// Quotes `value` so that the shell hooks are run with treats it as a single literal argument
+fn quote_shell(value: &str) -> String
+{
+    #[cfg(windows)]
+    {
+        return format!("\"{}\"", value.replace('"', "\"\""));
+    }
+    #[cfg(not(windows))]
+    {
+        // quotes cannot be escaped within single quotes, so the quote is closed and an escaped quote is added
+        return format!("'{}'", value.replace('\'', "'\\''"));
+    }
+}
+
+/// What happens when a hook fails to run or exits unsuccessfully
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HookFailure
+{
+    /// the command fails
+    #[default]
+    Fail,
+    /// a warning is logged and the remaining hooks are run
+    Warn,
+    Ignore
+}
+
+impl FromStr for HookFailure
+{
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        return match s
+        {
+            "fail" => Ok(Self::Fail),
+            "warn" => Ok(Self::Warn),
+            "ignore" => Ok(Self::Ignore),
+            _ => Err(s.to_string())
+        };
+    }
+}
+impl Display for HookFailure
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        return f.write_str(match self
+        {
+            Self::Fail => "fail",
+            Self::Warn => "warn",
+            Self::Ignore => "ignore"
+        });
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Error)]
@@ -30,8 +208,26 @@ pub enum ConfigError
     UnknownVariable(usize, String),
     #[error("Unknown property assignment \"{1}\" on line {0}")]
     UnknownProperty(usize, String),
-    #[error("Cannot place submodule outside the project directory: \"{1}\", on line {0}")]
-    DependencyOutsideProject(usize, String)
+    #[error("Cannot place dependency outside the project directory: \"{1}\", on line {0}")]
+    DependencyOutsideProject(usize, String),
+    #[error("Invalid block directive on line {0}")]
+    InvalidBlock(usize),
+    #[error("Block opened on line {0} is never closed")]
+    UnclosedBlock(usize),
+    #[error("Invalid file condition on line {0}")]
+    InvalidCondition(usize),
+    #[error("Template \"{0}\" extends itself through its parent templates")]
+    CyclicExtends(String),
+    #[error("Variable \"{1}\" declared on line {0} cannot have the value \"{2}\"")]
+    DisallowedValue(usize, String, String),
+    #[error("Variable \"{1}\" declared on line {0} expects a value of type {3}, not \"{2}\"")]
+    InvalidType(usize, String, String, VarType),
+    #[error("Default of variable \"{1}\" declared on line {0} references itself through other variables")]
+    CyclicVariable(usize, String),
+    #[error("Invalid loop in file name \"{0}\"")]
+    InvalidFileLoop(String),
+    #[error("Property \"{1}\" cannot be used with dependency \"{0}\" of kind {2}")]
+    InvalidDependencyProperty(String, String, DependencyKind)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -40,6 +236,10 @@ enum State
     Template,
     Subs,
     Deps,
+    Files,
+    Binary,
+    Vars,
+    Hooks,
     None
 }
 
@@ -54,8 +254,22 @@ impl Config
         let mut proj_name: Option<String> = None;
         let mut version: Option<Version> = None;
         let mut file_names: Option<bool> = None;
+        let mut preserve_times: Option<bool> = None;
+        let mut extends: Option<String> = None;
         let mut keys = Vec::new();
-        let mut deps = Vec::new();
+        // the kind of each dependency, which is only set by an attribute
+        let mut deps: Vec<(Dependency, Option<DependencyKind>)> = Vec::new();
+        let mut files = Vec::new();
+        let mut binary = Vec::new();
+        let mut vars = Vec::new();
+        let mut hooks: Vec<Hook> = Vec::new();
+        
+        // declarations are needed before any variables are used
+        if let Some(a) = args.as_mut()
+        {
+            vars = VarDecl::from_tokens(&tokens)?;
+            a.declare(&vars)?;
+        }
         
         for (t, i) in tokens
         {
@@ -69,6 +283,10 @@ impl Config
                     "template" => state = State::Template,
                     "subs" => state = State::Subs,
                     "deps" => state = State::Deps,
+                    "files" => state = State::Files,
+                    "binary" => state = State::Binary,
+                    "vars" => state = State::Vars,
+                    "hooks" => state = State::Hooks,
                     _ => return Err(ConfigError::UnknownTag(i, name.to_string()))
                 }
                 continue;
@@ -119,6 +337,22 @@ impl Config
                             
                             return Err(ConfigError::InvalidSyntax(i));
                         }
+                        if n == VAR_PRESERVE_TIMES
+                        {
+                            if preserve_times.is_some()
+                            {
+                                return Err(ConfigError::DuplicateProperty(VAR_PRESERVE_TIMES.to_string()));
+                            }
+                            
+                            let str = Object::group_to_string_err(v, |_, _| Err(ConfigError::InvalidSyntax(i)) )?;
+                            if let Ok(v) = bool::from_str(&str)
+                            {
+                                preserve_times = Some(v);
+                                continue;
+                            }
+                            
+                            return Err(ConfigError::InvalidSyntax(i));
+                        }
                         if n == VAR_VERSION
                         {
                             if version.is_some()
@@ -136,6 +370,18 @@ impl Config
                             return Err(ConfigError::InvalidSyntax(i));
                         }
                         
+                        if n == VAR_EXTENDS
+                        {
+                            if extends.is_some()
+                            {
+                                return Err(ConfigError::DuplicateProperty(VAR_EXTENDS.to_string()));
+                            }
+                            
+                            let str = Object::group_to_string_err(v, |_, _| Err(ConfigError::InvalidSyntax(i)) )?;
+                            extends = Some(str);
+                            continue;
+                        }
+                        
                         return Err(ConfigError::UnknownProperty(i, n));
                     }
                 },
@@ -161,6 +407,56 @@ impl Config
                 {
                     match t
                     {
+                        // attribute of the last dependency
+                        Token::Set(a, b) if a.get_abs().is_some_and(|n| n.starts_with('@')) =>
+                        {
+                            let attr = a.get_abs().unwrap_or_default().to_string();
+                            let value = Object::group_to_string_err(b, &mut lamda)?;
+                            let (last, kind) = deps.last_mut().ok_or(ConfigError::InvalidSyntax(i))?;
+                            
+                            let set = match attr.as_str()
+                            {
+                                ATTR_BRANCH => Some(&mut last.branch),
+                                ATTR_COMMIT =>
+                                {
+                                    // commits are fetched by their full sha1 or sha256 hash
+                                    if !is_commit_hash(&value)
+                                    {
+                                        return Err(ConfigError::InvalidSyntax(i));
+                                    }
+                                    Some(&mut last.commit)
+                                },
+                                ATTR_NAME => Some(&mut last.name),
+                                ATTR_DEPTH =>
+                                {
+                                    if last.depth.is_some()
+                                    {
+                                        return Err(ConfigError::DuplicateProperty(attr));
+                                    }
+                                    let depth = usize::from_str(&value).ok().filter(|d| *d > 0);
+                                    last.depth = Some(depth.ok_or(ConfigError::InvalidSyntax(i))?);
+                                    None
+                                },
+                                ATTR_KIND =>
+                                {
+                                    if kind.is_some()
+                                    {
+                                        return Err(ConfigError::DuplicateProperty(attr));
+                                    }
+                                    *kind = Some(DependencyKind::from_str(&value).map_err(|_| ConfigError::InvalidSyntax(i))?);
+                                    None
+                                },
+                                _ => return Err(ConfigError::UnknownProperty(i, attr))
+                            };
+                            if let Some(set) = set
+                            {
+                                if set.is_some()
+                                {
+                                    return Err(ConfigError::DuplicateProperty(attr));
+                                }
+                                *set = Some(value);
+                            }
+                        },
                         Token::Set(a, b) =>
                         {
                             let path = a.to_string_err(&mut lamda)?;
@@ -172,11 +468,66 @@ impl Config
                                 return Err(ConfigError::DependencyOutsideProject(i, path))
                             }
                             
-                            deps.push((path, url));
+                            deps.push((Dependency::new(path, url), None));
+                        },
+                        _ => return Err(ConfigError::InvalidSyntax(i))
+                    }
+                },
+                State::Files =>
+                {
+                    match t
+                    {
+                        Token::Set(a, b) =>
+                        {
+                            let pattern = match a.try_get_string()
+                            {
+                                Some(s) => s,
+                                None => return Err(ConfigError::InvalidSyntax(i))
+                            };
+                            let condition = Condition::from_objects(b)
+                                .ok_or(ConfigError::InvalidCondition(i))?;
+                            // should not get here without args
+                            let include = condition.evaluate(i, args.as_mut().unwrap())?;
+                            
+                            files.push(FileFilter { pattern, variable: condition.variable, include });
+                        },
+                        _ => return Err(ConfigError::InvalidSyntax(i))
+                    }
+                },
+                State::Binary =>
+                {
+                    match t
+                    {
+                        Token::Declare(v) =>
+                        {
+                            let pattern = Object::group_to_string_err(v, &mut lamda)?;
+                            binary.push(pattern);
+                        },
+                        _ => return Err(ConfigError::InvalidSyntax(i))
+                    }
+                },
+                State::Hooks =>
+                {
+                    match t
+                    {
+                        Token::Declare(v) =>
+                        {
+                            // values are quoted so that they are always a single argument, and are never run as commands
+                            let command = Object::group_to_string_err(v, |n, f| lamda(n, f).map(|value| quote_shell(&value)))?;
+                            hooks.push(Hook { command, on_error: HookFailure::Fail });
+                        },
+                        // attribute of the last hook
+                        Token::Set(a, b) if a.get_abs() == Some(ATTR_ON_ERROR) =>
+                        {
+                            let str = Object::group_to_string_err(b, |_, _| Err(ConfigError::InvalidSyntax(i)))?;
+                            let last = hooks.last_mut().ok_or(ConfigError::InvalidSyntax(i))?;
+                            last.on_error = HookFailure::from_str(&str).map_err(|_| ConfigError::InvalidSyntax(i))?;
                         },
                         _ => return Err(ConfigError::InvalidSyntax(i))
                     }
                 },
+                // already read
+                State::Vars => {},
                 State::None => return Err(ConfigError::InvalidSyntax(i)),
             }
         }
@@ -185,14 +536,73 @@ impl Config
         {
             return Err(ConfigError::MissingName);
         }
+        // attributes can be given before the kind
+        let deps: Vec<Dependency> = deps.into_iter()
+            .map(|(d, kind)| Dependency { kind: kind.unwrap_or_default(), ..d })
+            .collect();
+        for d in &deps
+        {
+            if let Some(a) = d.invalid_attribute()
+            {
+                return Err(ConfigError::InvalidDependencyProperty(d.path.clone(), a.to_string(), d.kind));
+            }
+        }
         
         return Ok(Config {
             name: proj_name.unwrap(),
             file_names: file_names.unwrap_or(false),
+            preserve_times: preserve_times.unwrap_or(false),
             version: version.unwrap_or(Version::ONE),
-            keys, deps
+            extends,
+            keys, deps, files, binary, vars, hooks
         });
     }
+    
+    /// Layers the contents of `parent` underneath this config, with entries in this config taking priority
+    pub fn inherit(&mut self, parent: Config)
+    {
+        for k in parent.keys
+        {
+            if !self.keys.iter().any(|s| s.0 == k.0)
+            {
+                self.keys.push(k);
+            }
+        }
+        for d in parent.deps
+        {
+            if !self.deps.iter().any(|s| s.path == d.path)
+            {
+                self.deps.push(d);
+            }
+        }
+        for f in parent.files
+        {
+            if !self.files.iter().any(|s| s.pattern == f.pattern)
+            {
+                self.files.push(f);
+            }
+        }
+        for b in parent.binary
+        {
+            if !self.binary.contains(&b)
+            {
+                self.binary.push(b);
+            }
+        }
+        for v in parent.vars
+        {
+            if !self.vars.iter().any(|s| s.name == v.name)
+            {
+                self.vars.push(v);
+            }
+        }
+        // parent hooks are run first, as their files are written first
+        let mut hooks: Vec<Hook> = parent.hooks.into_iter()
+            .filter(|h| !self.hooks.iter().any(|s| s.command == h.command))
+            .collect();
+        hooks.append(&mut self.hooks);
+        self.hooks = hooks;
+    }
 }
 
 fn dir_leaves_root(path: impl AsRef<std::path::Path>) -> bool
@@ -6,18 +6,22 @@ mod version;
 mod templates;
 mod backups;
 mod cases;
+mod vars;
 
 pub use version::*;
 pub use config::*;
 pub use templates::*;
 pub use backups::*;
 pub use cases::*;
+pub use vars::*;
 
 use crate::{VAR_DATE, VAR_NAME, VAR_TIME};
 
 pub trait VariableMap
 {
     fn map(&mut self, i: usize, v: &str, f: Option<String>) -> Result<String, ConfigError>;
+    /// Called with the variables declared by a config before any are mapped
+    fn declare(&mut self, _vars: &[VarDecl]) -> Result<(), ConfigError> { Ok(()) }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
@@ -25,7 +29,10 @@ pub struct ConfigArgs<'a>
 {
     pub map: HashMap<&'a str, &'a str>,
     pub date: DateTime<Local>,
-    pub name: &'a str
+    pub name: &'a str,
+    pub vars: Vec<VarDecl>,
+    /// the declared variables whose defaults are being resolved, to find cycles
+    resolving: Vec<String>
 }
 
 impl<'a> ConfigArgs<'a>
@@ -35,9 +42,54 @@ impl<'a> ConfigArgs<'a>
         return Self {
             map: HashMap::new(),
             date: Local::now(),
-            name
+            name,
+            vars: Vec::new(),
+            resolving: Vec::new()
         };
     }
+    
+    /// Evaluates the default of `decl`, mapping any variables it references
+    fn resolve_default(&mut self, decl: &VarDecl) -> Result<Option<String>, ConfigError>
+    {
+        let parts = match &decl.default
+        {
+            Some(p) => p,
+            None => return Ok(None)
+        };
+        
+        if self.resolving.contains(&decl.name)
+        {
+            return Err(ConfigError::CyclicVariable(decl.line, decl.name.clone()));
+        }
+        self.resolving.push(decl.name.clone());
+        
+        let mut value = String::new();
+        let mut result = Ok(());
+        for p in parts
+        {
+            match p
+            {
+                DefaultPart::Text(s) => value.push_str(s),
+                DefaultPart::Variable(n, f) =>
+                {
+                    match self.map(decl.line, n, f.clone())
+                    {
+                        Ok(s) => value.push_str(&s),
+                        Err(e) =>
+                        {
+                            result = Err(e);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        
+        self.resolving.pop();
+        result?;
+        decl.validate(&value)?;
+        return Ok(Some(value));
+    }
 }
 
 impl<'a> VariableMap for ConfigArgs<'a>
@@ -69,16 +121,60 @@ impl<'a> VariableMap for ConfigArgs<'a>
             VAR_TIME => Ok(self.date.format(format.unwrap_or("%H:%M:%S")).to_string()),
             _ =>
             {
-                let vt = self.map.get(v);
+                let decl = self.vars.iter().find(|d| d.name == v).cloned();
+                if let Some(s) = self.map.get(v)
+                {
+                    if let Some(d) = &decl
+                    {
+                        d.validate(s)?;
+                    }
+                    return Ok(s.to_string());
+                }
                 
-                return match vt
+                // fall back to the declared default
+                let value = match &decl
                 {
-                    Some(s) => Ok(s.to_string()),
-                    None => Err(ConfigError::UnknownVariable(i, v.to_string())),
+                    Some(d) => self.resolve_default(d)?,
+                    None => None
                 };
+                return value.ok_or(ConfigError::UnknownVariable(i, v.to_string()));
             }
         }
     }
+    /// Earlier declarations take priority, so that child templates can override parent templates.
+    /// Given values are validated here so that they are checked even if never used
+    fn declare(&mut self, vars: &[VarDecl]) -> Result<(), ConfigError>
+    {
+        for d in vars
+        {
+            if self.vars.iter().any(|v| v.name == d.name)
+            {
+                continue;
+            }
+            if let Some(v) = self.map.get(d.name.as_str())
+            {
+                d.validate(v)?;
+            }
+            self.vars.push(d.clone());
+        }
+        
+        return Ok(());
+    }
+}
+
+impl<T> VariableMap for &mut T
+    where T: VariableMap
+{
+    #[inline]
+    fn map(&mut self, i: usize, v: &str, f: Option<String>) -> Result<String, ConfigError>
+    {
+        return (**self).map(i, v, f);
+    }
+    #[inline]
+    fn declare(&mut self, vars: &[VarDecl]) -> Result<(), ConfigError>
+    {
+        return (**self).declare(vars);
+    }
 }
 impl VariableMap for ()
 {
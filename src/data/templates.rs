@@ -1,14 +1,29 @@
-use std::{collections::HashSet, fs, path::{Path, PathBuf}};
+use std::{collections::{HashMap, HashSet}, fs, path::{Path, PathBuf}, str::FromStr};
 use log::info;
 
-use crate::{duplicate_template, error::{IntoProjUpError, ProjUpError}, file::{self, traverse, Object, Token}, invalid_config, missing_path, missing_projup};
+use crate::{duplicate_template, error::{IntoProjUpError, ProjUpError}, file::{self, ops, traverse, Ignore, Object, Token}, invalid_config, missing_path, missing_projup};
 
-use super::Config;
+use super::{Config, Version, VersionReq};
+
+pub const ATTR_REF: &str = "@ref";
 
 pub struct Templates
 {
     location: String,
-    map: HashSet<String>
+    /// the folders of the templates, which are the template name, or the name and version
+    /// if there are multiple versions of the template
+    map: HashSet<String>,
+    /// templates that were cloned from a git repository
+    sources: HashMap<String, TemplateSource>
+}
+
+/// Where a template was cloned from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateSource
+{
+    pub url: String,
+    /// the tag or branch that was checked out, or the default branch if none
+    pub reference: Option<String>
 }
 
 impl Templates
@@ -17,7 +32,8 @@ impl Templates
     {
         return Self {
             location,
-            map: HashSet::new()
+            map: HashSet::new(),
+            sources: HashMap::new()
         };
     }
     
@@ -26,17 +42,37 @@ impl Templates
         let tokens = Token::from_content(content);
         
         let mut map = HashSet::new();
+        let mut sources = HashMap::new();
         let mut location = None;
+        let mut git = false;
+        // the last git template, which attributes apply to
+        let mut last: Option<String> = None;
         
         for (t, _) in tokens
         {
             match t
             {
-                Token::Declare(v) =>
+                Token::Declare(v) if !git =>
                 {
                     map.insert(Object::group_to_string_err(v, |_, _| Err(()))?);
                     continue;
                 },
+                Token::Set(a, v) if git =>
+                {
+                    let str = Object::group_to_string_err(v, |_, _| Err(()))?;
+                    if a.get_abs() == Some(ATTR_REF)
+                    {
+                        let source: &mut TemplateSource = last.as_ref().and_then(|l| sources.get_mut(l)).ok_or(())?;
+                        source.reference = Some(str);
+                        continue;
+                    }
+                    
+                    let name = a.try_get_string().ok_or(())?;
+                    map.insert(name.clone());
+                    sources.insert(name.clone(), TemplateSource { url: str, reference: None });
+                    last = Some(name);
+                    continue;
+                },
                 Token::Set(a, v) =>
                 {
                     if a.get_abs() == Some("location")
@@ -45,6 +81,11 @@ impl Templates
                         continue;
                     }
                 },
+                Token::Tag("git") =>
+                {
+                    git = true;
+                    continue;
+                },
                 _ => return Err(())
             }
             
@@ -56,7 +97,7 @@ impl Templates
             return Err(());
         }
         
-        return Ok(Templates { map, location: location.unwrap() });
+        return Ok(Templates { map, sources, location: location.unwrap() });
     }
     
     pub fn to_content(self) -> String
@@ -64,8 +105,24 @@ impl Templates
         let mut tokens = vec![Token::Set(Object::Absolute("location".to_string()), vec![Object::String(self.location)])];
         for s in self.map
         {
+            if self.sources.contains_key(&s)
+            {
+                continue;
+            }
             tokens.push(Token::Declare(vec![Object::String(s)]));
         }
+        if !self.sources.is_empty()
+        {
+            tokens.push(Token::Tag("git"));
+            for (n, s) in self.sources
+            {
+                tokens.push(Token::Set(Object::String(n), vec![Object::String(s.url)]));
+                if let Some(r) = s.reference
+                {
+                    tokens.push(Token::Set(Object::Absolute(ATTR_REF.to_string()), vec![Object::String(r)]));
+                }
+            }
+        }
         
         return Token::to_content(tokens.iter());
     }
@@ -89,6 +146,7 @@ impl Templates
     {
         return &self.location
     }
+    /// Gets the template in the folder `name`, which is also the template name if there is only one version
     pub fn try_get_template(&self, name: &str) -> Option<PathBuf>
     {
         if !self.map.contains(name)
@@ -98,56 +156,164 @@ impl Templates
         
         return Some(PathBuf::from_iter([&self.location, name]));
     }
+    /// Gets the highest version of the template `name` that matches `req`
+    pub fn try_get_version(&self, name: &str, req: &VersionReq) -> Result<Option<(PathBuf, Version)>, ProjUpError>
+    {
+        let mut result: Option<(PathBuf, Version)> = None;
+        for f in self.map.iter().filter(|f| split_template_name(f).0 == name)
+        {
+            let path = PathBuf::from_iter([&self.location, f]);
+            let version = template_config(&path)?.version;
+            if req.matches(&version) && result.as_ref().is_none_or(|r| version.cmp_precedence(&r.1).is_gt())
+            {
+                result = Some((path, version));
+            }
+        }
+        
+        return Ok(result);
+    }
+    /// Gets the highest version of the template `name`, which can be followed by an `@` and a version requirement
+    pub fn find(&self, name: &str) -> Result<PathBuf, ProjUpError>
+    {
+        let (name, req) = split_template_name(name);
+        let req = match req
+        {
+            Some(r) => VersionReq::from_str(r).map_err(|_| ProjUpError::InvalidVersionRequirement(r.to_string()))?,
+            None => VersionReq::ANY
+        };
+        
+        return match self.try_get_version(name, &req)?
+        {
+            Some((path, _)) => Ok(path),
+            None if self.try_get_version(name, &VersionReq::ANY)?.is_some() =>
+            {
+                Err(ProjUpError::NoMatchingVersion(name.to_string(), req.to_string()))
+            },
+            None => Err(ProjUpError::UnkownTemplate(name.to_string()))
+        };
+    }
+    /// The folder that the template with `config` should be placed in, where `current` is the folder it is already in
+    pub fn template_folder(&self, current: Option<&str>, config: &Config) -> Result<PathBuf, ProjUpError>
+    {
+        return template_folder(Path::new(&self.location), current, config);
+    }
+    pub fn contains(&self, name: &str) -> bool
+    {
+        return self.map.contains(name);
+    }
+    pub fn try_get_source(&self, name: &str) -> Option<&TemplateSource>
+    {
+        return self.sources.get(name);
+    }
+    /// The names and sources of all templates cloned from a git repository
+    pub fn sources(&self) -> impl Iterator<Item = (&String, &TemplateSource)>
+    {
+        return self.sources.iter();
+    }
+    /// Adds a template that was cloned from `source` into the template location
+    pub fn add_source(&mut self, name: String, source: TemplateSource)
+    {
+        self.map.insert(name.clone());
+        self.sources.insert(name, source);
+    }
+    pub fn try_remove(&mut self, name: &str) -> Option<TemplateSource>
+    {
+        self.map.remove(name);
+        return self.sources.remove(name);
+    }
     
     pub fn find_templates(&mut self, list: bool) -> Result<(), ProjUpError>
     {
         let mut map = HashSet::with_capacity(self.map.len());
+        let mut versions = Vec::new();
+        let location = PathBuf::from(&self.location);
+        let ignore_path = Path::new(&self.location).join(file::IGNORE_FILE);
+        let ignore = Ignore::from_dir(&self.location).projup(&ignore_path)?;
         
         return traverse::by_folder(self.location.as_ref(), |i|
         {
             let binding = i.file_name();
+            if ignore.is_ignored(Path::new(&binding), true)
+            {
+                return Ok(());
+            }
             let f_n = binding.to_string_lossy();
             if list
             {
                 info!("Opened {}", f_n);
             }
-            let p = i.path().join(".projup");
-            if !p.exists()
-            {
-                return missing_projup!(p);
-            }
-            let content = fs::read_to_string(&p).projup(&p)?;
-            let config = match Config::from_content::<()>(content.as_str(), None)
-            {
-                Ok(c) => c,
-                Err(e) => return invalid_config!(p, e)
-            };
-            if map.contains(&config.name)
+            let config = template_config(&i.path())?;
+            if versions.iter().any(|(n, v): &(String, Version)| *n == config.name && v.cmp_precedence(&config.version).is_eq())
             {
-                return duplicate_template!(config.name);
+                return duplicate_template!(format!("{}@{}", config.name, config.version));
             }
-            if list && config.name != f_n
+            if list && split_template_name(&f_n).0 != config.name
             {
                 info!("Discovered {}", &config.name);
             }
             
-            if i.file_name().as_os_str() != config.name.as_str()
+            let np = template_folder(&location, Some(&f_n), &config)?;
+            versions.push((config.name, config.version));
+            if np != i.path()
             {
-                let mut np = i.path();
-                np.pop();
-                np.push(&config.name);
-                if np.exists()
-                {
-                    return duplicate_template!(config.name);
-                }
-                fs::rename(i.path(), np).projup(i.path())?;
+                ops::rename(i.path(), &np).projup(i.path())?;
             }
             
-            map.insert(config.name);
+            // will be valid utf8 as it is made from the config
+            map.insert(np.file_name().unwrap_or_default().to_string_lossy().to_string());
             return Ok(());
         }).inspect(|_|
         {
+            self.sources.retain(|n, _| map.contains(n));
             self.map = map;
         });
     }
+}
+
+/// Splits `name@requirement` into the template name and version requirement
+pub fn split_template_name(name: &str) -> (&str, Option<&str>)
+{
+    return match name.split_once('@')
+    {
+        Some((n, r)) => (n, Some(r)),
+        None => (name, None)
+    };
+}
+
+/// Templates are placed in a folder of their name, or their name and version if another version already has that folder
+fn template_folder(location: &Path, current: Option<&str>, config: &Config) -> Result<PathBuf, ProjUpError>
+{
+    let versioned = format!("{}@{}", config.name, config.version);
+    if let Some(c) = current.filter(|c| *c == config.name || *c == versioned)
+    {
+        return Ok(location.join(c));
+    }
+    
+    let path = location.join(&config.name);
+    if !path.exists()
+    {
+        return Ok(path);
+    }
+    let path = location.join(&versioned);
+    if path.exists()
+    {
+        return duplicate_template!(versioned);
+    }
+    return Ok(path);
+}
+
+/// Reads the `[template]` section of the .projup file in the template directory `dir`
+pub fn template_config(dir: &Path) -> Result<Config, ProjUpError>
+{
+    let p = dir.join(".projup");
+    if !p.exists()
+    {
+        return missing_projup!(p);
+    }
+    let content = fs::read_to_string(&p).projup(&p)?;
+    return match Config::from_content::<()>(content.as_str(), None)
+    {
+        Ok(c) => Ok(c),
+        Err(e) => invalid_config!(p, e)
+    };
 }
\ No newline at end of file
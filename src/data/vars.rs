@@ -0,0 +1,305 @@
+use std::{fmt::Display, str::FromStr};
+use regex::Regex;
+
+use crate::{file::{Object, Token}, VAR_DATE, VAR_NAME, VAR_TIME};
+use super::{ConfigError, Version};
+
+pub const ATTR_DESCRIPTION: &str = "@description";
+pub const ATTR_VALUES: &str = "@values";
+pub const ATTR_TYPE: &str = "@type";
+pub const ATTR_PATTERN: &str = "@pattern";
+
+/// The kind of value a declared variable accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VarType
+{
+    #[default]
+    String,
+    Bool,
+    Int,
+    /// one of the `@values` of the variable
+    Enum,
+    /// matches the `@pattern` of the variable
+    Pattern,
+    Semver
+}
+
+impl FromStr for VarType
+{
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        return match s
+        {
+            "string" => Ok(Self::String),
+            "bool" => Ok(Self::Bool),
+            "int" => Ok(Self::Int),
+            "enum" => Ok(Self::Enum),
+            "pattern" => Ok(Self::Pattern),
+            "semver" => Ok(Self::Semver),
+            _ => Err(s.to_string())
+        };
+    }
+}
+impl Display for VarType
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        return f.write_str(match self
+        {
+            Self::String => "string",
+            Self::Bool => "bool",
+            Self::Int => "int",
+            Self::Enum => "enum",
+            Self::Pattern => "pattern",
+            Self::Semver => "semver"
+        });
+    }
+}
+
+/// A section of a default value, which can reference other variables
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DefaultPart
+{
+    Text(String),
+    /// 0 is the variable name, 1 is the format
+    Variable(String, Option<String>)
+}
+
+/// A variable declared in the `[vars]` section of a .projup file
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct VarDecl
+{
+    pub name: String,
+    pub default: Option<Vec<DefaultPart>>,
+    pub description: Option<String>,
+    pub var_type: VarType,
+    /// the allowed values, any value is allowed if empty
+    pub values: Vec<String>,
+    /// regex the whole value must match when the type is pattern
+    pub pattern: Option<String>,
+    /// the line the variable was declared on
+    pub line: usize
+}
+
+impl VarDecl
+{
+    pub fn new(name: String, line: usize) -> Self
+    {
+        return Self { name, line, ..Default::default() };
+    }
+    
+    /// Returns the declarations in all `[vars]` sections
+    pub fn from_tokens(tokens: &[(Token, usize)]) -> Result<Vec<VarDecl>, ConfigError>
+    {
+        let mut vars: Vec<VarDecl> = Vec::new();
+        let mut in_vars = false;
+        
+        for (t, i) in tokens
+        {
+            // so that they are shown as 1 based
+            let i = i + 1;
+            
+            if let Token::Tag(name) = t
+            {
+                in_vars = *name == "vars";
+                continue;
+            }
+            if !in_vars
+            {
+                continue;
+            }
+            
+            let (name, value) = match t.clone()
+            {
+                Token::Set(a, b) =>
+                {
+                    match a.try_get_string()
+                    {
+                        Some(s) => (s, Some(b)),
+                        None => return Err(ConfigError::InvalidSyntax(i))
+                    }
+                },
+                Token::Declare(v) => (Object::group_to_string_err(v, |_, _| Err(ConfigError::InvalidSyntax(i)))?, None),
+                _ => return Err(ConfigError::InvalidSyntax(i))
+            };
+            
+            // attribute of last variable
+            if name.starts_with('@')
+            {
+                let (last, str) = match (vars.last_mut(), value)
+                {
+                    (Some(l), Some(v)) => (l, Object::group_to_string_err(v, |_, _| Err(ConfigError::InvalidSyntax(i)))?),
+                    _ => return Err(ConfigError::InvalidSyntax(i))
+                };
+                
+                match name.as_str()
+                {
+                    ATTR_DESCRIPTION =>
+                    {
+                        if last.description.is_some()
+                        {
+                            return Err(ConfigError::DuplicateProperty(name));
+                        }
+                        last.description = Some(str);
+                    },
+                    ATTR_VALUES =>
+                    {
+                        if !last.values.is_empty()
+                        {
+                            return Err(ConfigError::DuplicateProperty(name));
+                        }
+                        last.values = str.split(',').map(|s| s.to_string()).collect();
+                    },
+                    ATTR_TYPE =>
+                    {
+                        if last.var_type != VarType::String
+                        {
+                            return Err(ConfigError::DuplicateProperty(name));
+                        }
+                        last.var_type = VarType::from_str(&str).map_err(|_| ConfigError::InvalidSyntax(i))?;
+                    },
+                    ATTR_PATTERN =>
+                    {
+                        if last.pattern.is_some()
+                        {
+                            return Err(ConfigError::DuplicateProperty(name));
+                        }
+                        if Regex::new(&str).is_err()
+                        {
+                            return Err(ConfigError::InvalidSyntax(i));
+                        }
+                        last.pattern = Some(str);
+                    },
+                    _ => return Err(ConfigError::UnknownProperty(i, name))
+                }
+                continue;
+            }
+            
+            if name == VAR_NAME || name == VAR_DATE || name == VAR_TIME || name.is_empty()
+            {
+                return Err(ConfigError::InvalidSyntax(i));
+            }
+            if vars.iter().any(|v| v.name == name)
+            {
+                return Err(ConfigError::DuplicateProperty(name));
+            }
+            
+            let mut decl = VarDecl::new(name, i);
+            decl.default = value.map(|v| v.into_iter().map(DefaultPart::from).collect());
+            vars.push(decl);
+        }
+        
+        for v in &vars
+        {
+            // types that need extra attributes
+            if (v.var_type == VarType::Enum && v.values.is_empty()) ||
+                (v.var_type == VarType::Pattern && v.pattern.is_none())
+            {
+                return Err(ConfigError::InvalidSyntax(v.line));
+            }
+            // computed defaults are checked when resolved
+            if let Some(d) = v.default_literal()
+            {
+                v.validate(&d)?;
+            }
+        }
+        
+        return Ok(vars);
+    }
+    
+    /// Returns the default if it does not reference any variables
+    pub fn default_literal(&self) -> Option<String>
+    {
+        let mut result = String::new();
+        for p in self.default.as_ref()?
+        {
+            match p
+            {
+                DefaultPart::Text(s) => result.push_str(s),
+                DefaultPart::Variable(_, _) => return None
+            }
+        }
+        
+        return Some(result);
+    }
+    /// Returns the default as written in the .projup file, for displaying
+    pub fn default_to_string(&self) -> Option<String>
+    {
+        let mut result = String::new();
+        for p in self.default.as_ref()?
+        {
+            match p
+            {
+                DefaultPart::Text(s) => result.push_str(s),
+                DefaultPart::Variable(v, None) =>
+                {
+                    result.push('$');
+                    result.push_str(v);
+                },
+                DefaultPart::Variable(v, Some(f)) =>
+                {
+                    result.push('$');
+                    result.push_str(v);
+                    result.push_str(":\"");
+                    result.push_str(f);
+                    result.push('"');
+                }
+            }
+        }
+        
+        return Some(result);
+    }
+    /// The names of the variables referenced by the default
+    pub fn references(&self) -> impl Iterator<Item = &str>
+    {
+        return self.default.iter().flatten().filter_map(|p| match p
+        {
+            DefaultPart::Variable(v, _) => Some(v.as_str()),
+            DefaultPart::Text(_) => None
+        });
+    }
+    
+    /// Checks that `value` is of the declared type and is one of the allowed values
+    pub fn validate(&self, value: &str) -> Result<(), ConfigError>
+    {
+        let valid = match self.var_type
+        {
+            VarType::String | VarType::Enum => true,
+            VarType::Bool => bool::from_str(value).is_ok(),
+            VarType::Int => i64::from_str(value).is_ok(),
+            VarType::Semver => Version::from_str(value).is_ok(),
+            VarType::Pattern =>
+            {
+                // checked when parsed
+                let pattern = self.pattern.as_deref().unwrap_or_default();
+                Regex::new(&format!("^(?:{pattern})$")).is_ok_and(|r| r.is_match(value))
+            }
+        };
+        if !valid
+        {
+            return Err(ConfigError::InvalidType(self.line, self.name.clone(), value.to_string(), self.var_type));
+        }
+        
+        if self.values.is_empty() || self.values.iter().any(|v| v == value)
+        {
+            return Ok(());
+        }
+        
+        return Err(ConfigError::DisallowedValue(self.line, self.name.clone(), value.to_string()));
+    }
+}
+impl<'a> From<Object<'a>> for DefaultPart
+{
+    fn from(value: Object<'a>) -> Self
+    {
+        return match value
+        {
+            Object::Absolute(s) => Self::Text(s),
+            Object::String(s) => Self::Text(s),
+            Object::Variable(v) => Self::Variable(v.to_string(), None),
+            Object::VariableFormat(v, f) => Self::Variable(v.to_string(), Some(f))
+        };
+    }
+}
\ No newline at end of file
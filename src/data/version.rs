@@ -1,18 +1,35 @@
-use std::{fmt::Display, num::ParseIntError, str::FromStr};
+use std::{cmp::Ordering, fmt::Display, num::ParseIntError, str::FromStr};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// A semantic version, where the minor and patch parts can be left out when parsing
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Version
 {
     pub major: usize,
     pub minor: usize,
-    pub patch: usize
+    pub patch: usize,
+    /// the dot separated identifiers after `-`, which make the version lower than the same version without them
+    pub pre: Vec<PreRelease>,
+    /// the dot separated identifiers after `+`, which are ignored when ordering versions
+    pub build: Vec<String>
+}
+
+/// An identifier of the pre-release part of a version
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PreRelease
+{
+    Numeric(usize),
+    Alpha(String)
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ParseVersionError
 {
     Int(ParseIntError),
-    Overflow
+    Overflow,
+    /// an empty or invalid pre-release identifier
+    PreRelease(String),
+    /// an empty or invalid build metadata identifier
+    Build(String)
 }
 impl ParseVersionError
 {
@@ -42,15 +59,127 @@ impl Version
     #[inline]
     pub const fn new(major: usize, minor: usize, patch: usize) -> Version
     {
-        return Version { major, minor, patch };
+        return Version { major, minor, patch, pre: Vec::new(), build: Vec::new() };
+    }
+    
+    #[inline]
+    pub fn is_pre_release(&self) -> bool
+    {
+        return !self.pre.is_empty();
+    }
+    /// Whether the major, minor and patch parts are the same
+    #[inline]
+    pub fn same_core(&self, other: &Version) -> bool
+    {
+        return self.major == other.major && self.minor == other.minor && self.patch == other.patch;
+    }
+    
+    /// Compares the versions ignoring build metadata, as semantic versioning defines
+    pub fn cmp_precedence(&self, other: &Version) -> Ordering
+    {
+        let core = (self.major, self.minor, self.patch).cmp(&(other.major, other.minor, other.patch));
+        if core != Ordering::Equal
+        {
+            return core;
+        }
+        
+        // a pre-release is lower than the release
+        return match (self.pre.is_empty(), other.pre.is_empty())
+        {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            // shorter is lower if all identifiers before are equal
+            (false, false) => self.pre.cmp(&other.pre)
+        };
     }
 }
-impl Display for Version
+impl Ord for Version
+{
+    /// Orders by precedence, then by build metadata so that the ordering is consistent with equality
+    fn cmp(&self, other: &Self) -> Ordering
+    {
+        return self.cmp_precedence(other).then_with(|| self.build.cmp(&other.build));
+    }
+}
+impl PartialOrd for Version
+{
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering>
+    {
+        return Some(self.cmp(other));
+    }
+}
+impl Ord for PreRelease
+{
+    /// Numeric identifiers are lower than alphanumeric identifiers
+    fn cmp(&self, other: &Self) -> Ordering
+    {
+        return match (self, other)
+        {
+            (Self::Numeric(a), Self::Numeric(b)) => a.cmp(b),
+            (Self::Numeric(_), Self::Alpha(_)) => Ordering::Less,
+            (Self::Alpha(_), Self::Numeric(_)) => Ordering::Greater,
+            (Self::Alpha(a), Self::Alpha(b)) => a.cmp(b)
+        };
+    }
+}
+impl PartialOrd for PreRelease
 {
     #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering>
+    {
+        return Some(self.cmp(other));
+    }
+}
+impl Display for Version
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        for (i, p) in self.pre.iter().enumerate()
+        {
+            f.write_str(if i == 0 { "-" } else { "." })?;
+            write!(f, "{p}")?;
+        }
+        if !self.build.is_empty()
+        {
+            write!(f, "+{}", self.build.join("."))?;
+        }
+        return Ok(());
+    }
+}
+impl Display for PreRelease
+{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
     {
-        return write!(f, "{}.{}.{}", self.major, self.minor, self.patch);
+        return match self
+        {
+            Self::Numeric(n) => write!(f, "{n}"),
+            Self::Alpha(s) => f.write_str(s)
+        };
+    }
+}
+impl FromStr for PreRelease
+{
+    type Err = ParseVersionError;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        if !valid_identifier(s)
+        {
+            return Err(ParseVersionError::PreRelease(s.to_string()));
+        }
+        if !s.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Ok(Self::Alpha(s.to_string()));
+        }
+        // numeric identifiers cannot have leading zeros
+        if s.len() > 1 && s.starts_with('0')
+        {
+            return Err(ParseVersionError::PreRelease(s.to_string()));
+        }
+        return usize::from_str(s).map(Self::Numeric).map_err(|_| ParseVersionError::PreRelease(s.to_string()));
     }
 }
 impl FromStr for Version
@@ -59,6 +188,17 @@ impl FromStr for Version
 
     fn from_str(s: &str) -> Result<Self, Self::Err>
     {
+        let (s, build) = match s.split_once('+')
+        {
+            Some((v, b)) => (v, Some(b)),
+            None => (s, None)
+        };
+        let (s, pre) = match s.split_once('-')
+        {
+            Some((v, p)) => (v, Some(p)),
+            None => (s, None)
+        };
+        
         let mut a: [Option<usize>; 3] = [None, None, None];
         let mut index = 0;
         
@@ -83,10 +223,32 @@ impl FromStr for Version
         }
         
         let b = a.map(|o| o.unwrap_or(0));
-        return Ok(Version::new(b[0], b[1], b[2]));
+        let mut version = Version::new(b[0], b[1], b[2]);
+        if let Some(p) = pre
+        {
+            version.pre = p.split('.').map(PreRelease::from_str).collect::<Result<_, _>>()?;
+        }
+        if let Some(b) = build
+        {
+            for id in b.split('.')
+            {
+                if !valid_identifier(id)
+                {
+                    return Err(ParseVersionError::Build(id.to_string()));
+                }
+                version.build.push(id.to_string());
+            }
+        }
+        return Ok(version);
     }
 }
 
+/// Identifiers are non empty and only contain ascii alphanumerics and hyphens
+fn valid_identifier(s: &str) -> bool
+{
+    return !s.is_empty() && s.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-');
+}
+
 impl From<[usize; 3]> for Version {
     fn from(value: [usize; 3]) -> Self
     {
@@ -98,4 +260,179 @@ impl From<Version> for [usize; 3] {
     {
         return [value.major, value.minor, value.patch];
     }
+}
+
+/// How a version is compared in a `VersionReq`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionOp
+{
+    /// `=`, the given parts must be equal
+    Exact,
+    /// `^` or no operator, compatible versions that do not change the first non zero part
+    Caret,
+    /// `~`, only the last given part or patch can change
+    Tilde,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq
+}
+
+/// A single comparison of a version requirement, such as `>=1.2`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparator
+{
+    pub op: VersionOp,
+    pub version: Version,
+    /// the number of parts given, as `1.2` matches differently to `1.2.0`
+    pub parts: usize
+}
+
+/// Comma separated comparators that a version must all match, in the style of semantic versioning requirements
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionReq
+{
+    pub comparators: Vec<Comparator>
+}
+
+impl Comparator
+{
+    /// The smallest version above all versions starting with the first `parts` parts of the version
+    fn bump(&self, parts: usize) -> Version
+    {
+        let v = &self.version;
+        return match parts
+        {
+            1 => Version::new(v.major + 1, 0, 0),
+            2 => Version::new(v.major, v.minor + 1, 0),
+            _ => Version::new(v.major, v.minor, v.patch + 1)
+        };
+    }
+    
+    /// Build metadata is ignored
+    pub fn matches(&self, version: &Version) -> bool
+    {
+        let lower = version.cmp_precedence(&self.version);
+        let below = |parts: usize| version.cmp_precedence(&self.bump(parts)).is_lt();
+        // a full version is compared exactly, otherwise any version starting with the given parts is included
+        let full = self.parts >= 3;
+        return match self.op
+        {
+            VersionOp::Exact if full => lower.is_eq(),
+            VersionOp::Exact => lower.is_ge() && below(self.parts),
+            VersionOp::Greater if full => lower.is_gt(),
+            VersionOp::Greater => !below(self.parts),
+            VersionOp::GreaterEq => lower.is_ge(),
+            VersionOp::Less => lower.is_lt(),
+            VersionOp::LessEq if full => lower.is_le(),
+            VersionOp::LessEq => below(self.parts),
+            VersionOp::Tilde => lower.is_ge() && below(self.parts.min(2)),
+            VersionOp::Caret =>
+            {
+                let parts = if self.version.major > 0 || self.parts == 1 { 1 }
+                    else if self.version.minor > 0 || self.parts == 2 { 2 }
+                    else { 3 };
+                lower.is_ge() && below(parts)
+            }
+        };
+    }
+}
+impl VersionReq
+{
+    /// Matches any version
+    pub const ANY: VersionReq = VersionReq { comparators: Vec::new() };
+    
+    /// Only matches `version`
+    pub fn exact(version: Version) -> Self
+    {
+        return VersionReq { comparators: vec![Comparator { op: VersionOp::Exact, version, parts: 3 }] };
+    }
+    
+    /// Pre-release versions only match if a comparator is a pre-release of the same version,
+    /// so that they are not selected unless asked for
+    pub fn matches(&self, version: &Version) -> bool
+    {
+        if version.is_pre_release() &&
+            !self.comparators.iter().any(|c| c.version.is_pre_release() && c.version.same_core(version))
+        {
+            return false;
+        }
+        
+        return self.comparators.iter().all(|c| c.matches(version));
+    }
+}
+impl FromStr for Comparator
+{
+    type Err = ParseVersionError;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        let s = s.trim();
+        // longer operators first
+        let (op, rest) = [(">=", VersionOp::GreaterEq), ("<=", VersionOp::LessEq), (">", VersionOp::Greater),
+            ("<", VersionOp::Less), ("=", VersionOp::Exact), ("^", VersionOp::Caret), ("~", VersionOp::Tilde)]
+            .into_iter()
+            .find_map(|(p, op)| s.strip_prefix(p).map(|r| (op, r.trim_start())))
+            .unwrap_or((VersionOp::Caret, s));
+        
+        let version = Version::from_str(rest)?;
+        // only the major, minor and patch parts
+        let parts = rest.split(['-', '+']).next().unwrap_or_default().split('.').count();
+        return Ok(Comparator { op, version, parts });
+    }
+}
+impl FromStr for VersionReq
+{
+    type Err = ParseVersionError;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+        if s.trim() == "*"
+        {
+            return Ok(Self::ANY);
+        }
+        
+        let comparators = s.split(',')
+            .map(Comparator::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        return Ok(VersionReq { comparators });
+    }
+}
+impl Display for VersionReq
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result
+    {
+        if self.comparators.is_empty()
+        {
+            return f.write_str("*");
+        }
+        
+        for (i, c) in self.comparators.iter().enumerate()
+        {
+            if i > 0
+            {
+                f.write_str(", ")?;
+            }
+            let op = match c.op
+            {
+                VersionOp::Exact => "=",
+                VersionOp::Caret => "^",
+                VersionOp::Tilde => "~",
+                VersionOp::Greater => ">",
+                VersionOp::GreaterEq => ">=",
+                VersionOp::Less => "<",
+                VersionOp::LessEq => "<="
+            };
+            let v = &c.version;
+            let core = [v.major, v.minor, v.patch];
+            let parts: Vec<String> = core[..c.parts.min(3)].iter().map(|p| p.to_string()).collect();
+            write!(f, "{op}{}", parts.join("."))?;
+            for (i, p) in v.pre.iter().enumerate()
+            {
+                f.write_str(if i == 0 { "-" } else { "." })?;
+                write!(f, "{p}")?;
+            }
+        }
+        return Ok(());
+    }
 }
\ No newline at end of file
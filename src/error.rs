@@ -42,7 +42,25 @@ pub enum ProjUpError
     #[error("Git operation error: {0}")]
     GitError(String),
     #[error("The backup location {0} could not be accessed")]
-    BackupUnavailable(String)
+    BackupUnavailable(String),
+    #[error("Invalid template file {0}\n\t{1}")]
+    InvalidTemplateFile(PathBuf, ConfigError),
+    #[error("Template \"{0}\" was not added from a git repository")]
+    UntrackedTemplate(String),
+    #[error("Invalid version requirement \"{0}\"")]
+    InvalidVersionRequirement(String),
+    #[error("No version of template \"{0}\" matches \"{1}\"")]
+    NoMatchingVersion(String, String),
+    #[error("Template paths {0} and {1} are both written to {2}")]
+    PathCollision(PathBuf, PathBuf, PathBuf),
+    #[error("Hook \"{0}\" failed: {1}")]
+    HookError(String, String),
+    #[error("Invalid commit message\n\t{0}")]
+    InvalidCommitMessage(ConfigError),
+    #[error("Template path {0} is renamed to \"{1}\", which is not a single file or directory name")]
+    InvalidRenderedName(PathBuf, String),
+    #[error("Project \"{0}\" has uncommitted changes, which would be included in the commit")]
+    UncommittedChanges(String)
 }
 
 impl ProjUpError
@@ -99,6 +117,14 @@ macro_rules! invalid_config
     }}
 }
 #[macro_export]
+macro_rules! invalid_template_file
+{
+    ($path:expr, $config:expr) =>
+    {{
+        Err(ProjUpError::InvalidTemplateFile($path, $config))
+    }}
+}
+#[macro_export]
 macro_rules! missing_path
 {
     ($path:expr) =>
@@ -0,0 +1,610 @@
+use std::{borrow::Cow, str::FromStr};
+
+use crate::data::{convert_case, Cases, ConfigError, VarDecl, VariableMap};
+
+use super::Object;
+
+/// Start of a block directive line, e.g. `$[if $with_ci]`
+pub const BLOCK_START: &str = "$[";
+/// End of a block directive line
+pub const BLOCK_END: &str = "]";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition
+{
+    pub negate: bool,
+    pub variable: String,
+    pub format: Option<String>,
+    /// compared against the variable if `Some`, otherwise the variable is tested for truth
+    pub value: Option<String>
+}
+
+impl Condition
+{
+    /// Parses `[!]$var[:"format"] [(== | !=) value]`
+    pub fn parse(text: &str) -> Option<Condition>
+    {
+        let mut text = text.trim();
+        let mut negate = false;
+        
+        if let Some(t) = text.strip_prefix('!')
+        {
+            negate = true;
+            text = t.trim_start();
+        }
+        
+        let (variable, format, rest) = read_variable(text)?;
+        let rest = rest.trim();
+        if rest.is_empty()
+        {
+            return Some(Condition { negate, variable: variable.to_string(), format, value: None });
+        }
+        // cannot negate and compare
+        if negate
+        {
+            return None;
+        }
+        
+        let (equal, value) = if let Some(v) = rest.strip_prefix("==")
+        {
+            (true, v)
+        }
+        else if let Some(v) = rest.strip_prefix("!=")
+        {
+            (false, v)
+        }
+        else { return None; };
+        
+        return Some(Condition {
+            negate: !equal,
+            variable: variable.to_string(),
+            format,
+            value: Some(read_value(value.trim())?)
+        });
+    }
+    
+    /// Creates a condition from the objects of a .projup line, taking the same form as `parse`
+    pub fn from_objects(objs: Vec<Object>) -> Option<Condition>
+    {
+        let mut it = objs.into_iter().peekable();
+        let negate = it.next_if(|o| o.get_abs() == Some("!")).is_some();
+        
+        let (variable, format) = match it.next()?
+        {
+            Object::Variable(v) => (v.to_string(), None),
+            Object::VariableFormat(v, f) => (v.to_string(), Some(f)),
+            _ => return None
+        };
+        
+        let op = match it.next()
+        {
+            Some(o) => o.try_get_string()?,
+            None => return Some(Condition { negate, variable, format, value: None })
+        };
+        // cannot negate and compare
+        if negate
+        {
+            return None;
+        }
+        
+        let (equal, mut value) = if let Some(v) = op.strip_prefix("==")
+        {
+            (true, v.to_string())
+        }
+        else if let Some(v) = op.strip_prefix("!=")
+        {
+            (false, v.to_string())
+        }
+        else { return None; };
+        
+        for o in it
+        {
+            value.push_str(&o.try_get_string()?);
+        }
+        
+        return Some(Condition { negate: !equal, variable, format, value: Some(value) });
+    }
+    
+    pub fn evaluate<T>(&self, line: usize, vars: &mut T) -> Result<bool, ConfigError>
+        where T: VariableMap
+    {
+        let v = vars.map(line, &self.variable, self.format.clone())?;
+        
+        let result = match &self.value
+        {
+            Some(value) => &v == value,
+            None => is_truthy(&v)
+        };
+        
+        return Ok(result != self.negate);
+    }
+}
+
+/// A repeat block over the elements of a comma separated list variable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop
+{
+    /// name each element is given within the block
+    pub item: String,
+    pub variable: String,
+    pub format: Option<String>
+}
+
+impl Loop
+{
+    /// Parses `item in $var[:"format"]`
+    pub fn parse(text: &str) -> Option<Loop>
+    {
+        let text = text.trim();
+        let end = text.find(char::is_whitespace)?;
+        let (item, rest) = text.split_at(end);
+        if !is_name(item)
+        {
+            return None;
+        }
+        
+        let rest = rest.trim_start().strip_prefix("in")?;
+        if !rest.starts_with(char::is_whitespace)
+        {
+            return None;
+        }
+        let (variable, format, rest) = read_variable(rest.trim_start())?;
+        if !rest.trim().is_empty()
+        {
+            return None;
+        }
+        
+        return Some(Loop { item: item.to_string(), variable: variable.to_string(), format });
+    }
+    
+    /// The elements of the list, which is empty if the variable is empty
+    pub fn items<T>(&self, line: usize, vars: &mut T) -> Result<Vec<String>, ConfigError>
+        where T: VariableMap
+    {
+        let v = vars.map(line, &self.variable, self.format.clone())?;
+        
+        return Ok(v.split(',')
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string())
+            .collect());
+    }
+    
+    /// The element and index variables for the element at `index`
+    pub fn scope(&self, index: usize, item: String) -> [(String, String); 2]
+    {
+        return [(self.item.clone(), item), (format!("{}_index", self.item), index.to_string())];
+    }
+}
+
+/// Variables defined by the enclosing loops, which are checked before `vars`
+pub struct Scope<T>
+{
+    pub vars: T,
+    pub items: Vec<(String, String)>
+}
+
+impl<T> VariableMap for Scope<T>
+    where T: VariableMap
+{
+    fn map(&mut self, i: usize, v: &str, f: Option<String>) -> Result<String, ConfigError>
+    {
+        // inner loops take priority
+        return match self.items.iter().rev().find(|(n, _)| n == v)
+        {
+            Some((_, value)) => Ok(format_item(value, f.as_deref())),
+            None => self.vars.map(i, v, f)
+        };
+    }
+    fn declare(&mut self, vars: &[VarDecl]) -> Result<(), ConfigError>
+    {
+        return self.vars.declare(vars);
+    }
+}
+
+/// Splits a file name starting with `$[for item in $var]` into the loop and the rest of the name.
+/// Returns `None` if the name does not start with a loop, or `Some(Err)` if it is an invalid one
+pub fn split_name_loop(name: &str) -> Option<Result<(Loop, &str), ()>>
+{
+    let rest = name.strip_prefix(BLOCK_START)?.strip_prefix("for")?;
+    if !rest.starts_with(char::is_whitespace)
+    {
+        return None;
+    }
+    
+    let end = match rest.find(BLOCK_END)
+    {
+        Some(e) => e,
+        None => return Some(Err(()))
+    };
+    return Some(Loop::parse(&rest[..end])
+        .map(|l| (l, &rest[(end + BLOCK_END.len())..]))
+        .ok_or(()));
+}
+
+/// Replaces `$[item]` and `$[item:"format"]` references to loop variables within `text`,
+/// leaving any other `$[` untouched
+pub fn replace_loop_refs<'a>(text: &'a str, items: &[(String, String)]) -> Cow<'a, str>
+{
+    if items.is_empty() || !text.contains(BLOCK_START)
+    {
+        return Cow::Borrowed(text);
+    }
+    
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(BLOCK_START)
+    {
+        result.push_str(&rest[..start]);
+        let after = &rest[(start + BLOCK_START.len())..];
+        
+        // variable syntax is the same as in conditions, just without the $
+        let found = read_variable(&format!("${after}"))
+            .and_then(|(n, f, r)|
+            {
+                let r = r.strip_prefix(BLOCK_END)?;
+                let (_, value) = items.iter().rev().find(|(v, _)| v == n)?;
+                Some((format_item(value, f.as_deref()), after.len() - r.len()))
+            });
+        
+        match found
+        {
+            Some((value, len)) =>
+            {
+                result.push_str(&value);
+                rest = &after[len..];
+            },
+            None =>
+            {
+                result.push_str(BLOCK_START);
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+    
+    return Cow::Owned(result);
+}
+
+/// Loop elements only take case formats
+fn format_item(value: &str, format: Option<&str>) -> String
+{
+    return format.and_then(|f| Cases::from_str(f).ok())
+        .map(|c| convert_case(value, c))
+        .unwrap_or(value.to_string());
+}
+fn is_name(text: &str) -> bool
+{
+    return !text.is_empty() && text.chars().all(|c| c.is_alphanumeric() || c == '_');
+}
+
+/// Whether a variable value counts as true in a condition
+pub fn is_truthy(value: &str) -> bool
+{
+    let value = value.trim();
+    return !(value.is_empty() ||
+        value == "0" ||
+        value.eq_ignore_ascii_case("false") ||
+        value.eq_ignore_ascii_case("no") ||
+        value.eq_ignore_ascii_case("off"));
+}
+
+/// Reads a `$var` or `$var:"format"` from the start of `text`, returning the remaining text
+pub(super) fn read_variable(text: &str) -> Option<(&str, Option<String>, &str)>
+{
+    let text = text.strip_prefix('$')?;
+    let end = text.find(|c: char| !c.is_alphanumeric() && c != '_')
+        .unwrap_or(text.len());
+    if end == 0
+    {
+        return None;
+    }
+    
+    let (name, rest) = text.split_at(end);
+    let rest = match rest.strip_prefix(':')
+    {
+        Some(r) => r,
+        None => return Some((name, None, rest))
+    };
+    
+    let mut format = String::new();
+    let mut bs = false;
+    for (i, c) in rest.strip_prefix('"')?.char_indices()
+    {
+        if bs
+        {
+            bs = false;
+            format.push(c);
+            continue;
+        }
+        match c
+        {
+            '\\' => bs = true,
+            // + 2 for both quotes
+            '"' => return Some((name, Some(format), &rest[(i + 2)..])),
+            _ => format.push(c)
+        }
+    }
+    
+    return None;
+}
+/// Reads a plain or quoted value
+fn read_value(text: &str) -> Option<String>
+{
+    let inner = match text.strip_prefix('"')
+    {
+        Some(t) => t.strip_suffix('"')?,
+        None => return Some(text.to_string())
+    };
+    
+    let mut value = String::with_capacity(inner.len());
+    let mut bs = false;
+    for c in inner.chars()
+    {
+        if bs
+        {
+            bs = false;
+            value.push(c);
+            continue;
+        }
+        if c == '\\'
+        {
+            bs = true;
+            continue;
+        }
+        value.push(c);
+    }
+    
+    return Some(value);
+}
+
+enum Directive
+{
+    If(Condition),
+    Elif(Condition),
+    Else,
+    For(Loop),
+    End
+}
+
+/// Returns `None` if the line is not a directive, or `Some(Err)` if it is an invalid one
+fn read_directive(line: &str) -> Option<Result<Directive, ()>>
+{
+    let inner = line.trim()
+        .strip_prefix(BLOCK_START)?
+        .strip_suffix(BLOCK_END)?
+        .trim();
+    
+    let (word, rest) = inner.split_at(inner.find(char::is_whitespace).unwrap_or(inner.len()));
+    let d = match word
+    {
+        "if" => Condition::parse(rest).map(Directive::If),
+        "elif" => Condition::parse(rest).map(Directive::Elif),
+        "else" => rest.trim().is_empty().then_some(Directive::Else),
+        "for" => Loop::parse(rest).map(Directive::For),
+        "end" => rest.trim().is_empty().then_some(Directive::End),
+        // not a directive, e.g. a loop variable reference
+        _ => return None
+    };
+    
+    return Some(d.ok_or(()));
+}
+
+struct Branch<'a>
+{
+    /// line of the directive
+    line: usize,
+    /// `None` for else
+    condition: Option<Condition>,
+    body: Vec<Node<'a>>
+}
+
+enum Node<'a>
+{
+    Text(&'a str),
+    If(Vec<Branch<'a>>),
+    For(usize, Loop, Vec<Node<'a>>)
+}
+
+/// A block that has not been closed yet
+enum Open<'a>
+{
+    If(Vec<Branch<'a>>, bool),
+    For(Loop)
+}
+
+/// Splits `text` into lines and the blocks containing them
+fn read_nodes(text: &str) -> Result<Vec<Node<'_>>, ConfigError>
+{
+    // line of the opening directive, the block and the nodes before it
+    let mut stack: Vec<(usize, Open, Vec<Node>)> = Vec::new();
+    let mut nodes = Vec::new();
+    
+    for (index, line) in text.split_inclusive('\n').enumerate()
+    {
+        // so that they are shown as 1 based
+        let i = index + 1;
+        
+        let d = match read_directive(line)
+        {
+            Some(Ok(d)) => d,
+            Some(Err(_)) => return Err(ConfigError::InvalidBlock(i)),
+            None =>
+            {
+                nodes.push(Node::Text(line));
+                continue;
+            }
+        };
+        
+        match d
+        {
+            Directive::If(c) =>
+            {
+                let branch = Branch { line: i, condition: Some(c), body: Vec::new() };
+                stack.push((i, Open::If(vec![branch], false), std::mem::take(&mut nodes)));
+            },
+            Directive::Elif(_) | Directive::Else =>
+            {
+                let (branches, has_else) = match stack.last_mut()
+                {
+                    Some((_, Open::If(b, has_else), _)) if !*has_else => (b, has_else),
+                    _ => return Err(ConfigError::InvalidBlock(i))
+                };
+                
+                // will have at least the if branch
+                branches.last_mut().unwrap().body = std::mem::take(&mut nodes);
+                let condition = match d
+                {
+                    Directive::Elif(c) => Some(c),
+                    _ =>
+                    {
+                        *has_else = true;
+                        None
+                    }
+                };
+                branches.push(Branch { line: i, condition, body: Vec::new() });
+            },
+            Directive::For(l) => stack.push((i, Open::For(l), std::mem::take(&mut nodes))),
+            Directive::End =>
+            {
+                let (line, open, outer) = stack.pop().ok_or(ConfigError::InvalidBlock(i))?;
+                let body = std::mem::replace(&mut nodes, outer);
+                
+                nodes.push(match open
+                {
+                    Open::If(mut branches, _) =>
+                    {
+                        branches.last_mut().unwrap().body = body;
+                        Node::If(branches)
+                    },
+                    Open::For(l) => Node::For(line, l, body)
+                });
+            }
+        }
+    }
+    
+    if let Some((line, _, _)) = stack.pop()
+    {
+        return Err(ConfigError::UnclosedBlock(line));
+    }
+    
+    return Ok(nodes);
+}
+
+fn write_nodes<T>(nodes: &[Node], vars: &mut Scope<T>, result: &mut String) -> Result<(), ConfigError>
+    where T: VariableMap
+{
+    for n in nodes
+    {
+        match n
+        {
+            Node::Text(line) => result.push_str(&replace_loop_refs(line, &vars.items)),
+            Node::If(branches) =>
+            {
+                // conditions after the taken branch are not needed
+                for b in branches
+                {
+                    let taken = match &b.condition
+                    {
+                        Some(c) => c.evaluate(b.line, vars)?,
+                        None => true
+                    };
+                    if taken
+                    {
+                        write_nodes(&b.body, vars, result)?;
+                        break;
+                    }
+                }
+            },
+            Node::For(line, l, body) =>
+            {
+                for (index, item) in l.items(*line, vars)?.into_iter().enumerate()
+                {
+                    vars.items.extend(l.scope(index, item));
+                    let r = write_nodes(body, vars, result);
+                    vars.items.truncate(vars.items.len() - 2);
+                    r?;
+                }
+            }
+        }
+    }
+    
+    return Ok(());
+}
+
+/// Adds the variables referenced by `nodes` to `result`, apart from those defined by the loops in `items`
+fn collect_variables(nodes: &[Node], items: &mut Vec<String>, result: &mut Vec<String>)
+{
+    fn add(v: &str, items: &[String], result: &mut Vec<String>)
+    {
+        if !items.iter().any(|i| i == v) && !result.iter().any(|r| r == v)
+        {
+            result.push(v.to_string());
+        }
+    }
+    
+    for n in nodes
+    {
+        match n
+        {
+            Node::Text(_) => {},
+            Node::If(branches) =>
+            {
+                for b in branches
+                {
+                    if let Some(c) = &b.condition
+                    {
+                        add(&c.variable, items, result);
+                    }
+                }
+                for b in branches
+                {
+                    collect_variables(&b.body, items, result);
+                }
+            },
+            Node::For(_, l, body) =>
+            {
+                add(&l.variable, items, result);
+                items.extend(l.scope(0, String::new()).map(|(n, _)| n));
+                collect_variables(body, items, result);
+                items.truncate(items.len() - 2);
+            }
+        }
+    }
+}
+
+/// Returns the variables referenced by the conditional and repeat blocks within `text`,
+/// apart from the variables defined by its loops
+pub fn block_variables(text: &str) -> Result<Vec<String>, ConfigError>
+{
+    let mut result = Vec::new();
+    if !text.contains(BLOCK_START)
+    {
+        return Ok(result);
+    }
+    
+    collect_variables(&read_nodes(text)?, &mut Vec::new(), &mut result);
+    return Ok(result);
+}
+
+/// Evaluates the conditional and repeat blocks within `text`, removing directive lines and any inactive sections
+pub fn parse_blocks<'a, T>(text: &'a str, vars: &mut T) -> Result<Cow<'a, str>, ConfigError>
+    where T: VariableMap
+{
+    return parse_blocks_scoped(text, vars, Vec::new());
+}
+/// Same as `parse_blocks`, with `items` being variables of an enclosing loop, e.g. from the file name
+pub fn parse_blocks_scoped<'a, T>(text: &'a str, vars: &mut T, items: Vec<(String, String)>) -> Result<Cow<'a, str>, ConfigError>
+    where T: VariableMap
+{
+    if !text.contains(BLOCK_START)
+    {
+        return Ok(Cow::Borrowed(text));
+    }
+    
+    let nodes = read_nodes(text)?;
+    let mut result = String::with_capacity(text.len());
+    write_nodes(&nodes, &mut Scope { vars, items }, &mut result)?;
+    
+    return Ok(Cow::Owned(result));
+}
\ No newline at end of file
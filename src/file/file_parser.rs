@@ -1,136 +1,346 @@
-use std::cmp::Ordering;
+use std::{collections::VecDeque, io::{self, Read, Write}};
 
+use super::BLOCK_START;
+
+/// Ends a search, only reached after a match has been found
+const DEAD: usize = 0;
+const ROOT: usize = 1;
+
+struct State
+{
+    /// sorted by byte
+    next: Vec<(u8, usize)>,
+    fail: usize,
+    depth: usize,
+    /// index of the key that is the leftmost longest match when this state is reached
+    key: Option<usize>
+}
+
+/// An Aho-Corasick automaton over the keys to substitute, giving leftmost longest matches.
+/// Empty keys are ignored, and the first of any duplicate keys is used
 pub struct ParserData<'a>
 {
     keys: &'a [(String, String)],
-    map: [bool; 256]
+    states: Vec<State>,
+    /// transitions from the root, which are all set
+    root: [usize; 256],
+    /// length of the longest key in bytes
+    max_len: usize
 }
 
 impl<'a> ParserData<'a>
 {
     pub fn new(keys: &'a [(String, String)]) -> Self
     {
-        let mut map = [false; 256];
+        let mut pd = Self {
+            keys,
+            states: vec![State::new(0), State::new(0)],
+            root: [ROOT; 256],
+            max_len: 0
+        };
         
-        for k in keys
+        for (i, k) in keys.iter().enumerate()
+        {
+            pd.insert(i, k.0.as_bytes());
+        }
+        for (b, s) in &pd.states[ROOT].next
         {
-            map[k.0.as_bytes()[0] as usize] = true;
+            pd.root[*b as usize] = *s;
         }
+        pd.fill_fail();
         
-        return Self { keys, map };
+        return pd;
     }
-}
-
-/// `pd.keys` must be sorted by first string
-pub fn parse(text: &str, pd: &ParserData) -> Vec<u8>
-{
-    let mut result = Vec::with_capacity(text.len());
     
-    let mut it = text.bytes().enumerate();
-    while let Some((i, c)) = it.next()
+    fn insert(&mut self, index: usize, key: &[u8])
     {
-        // no point checking
-        if !pd.map[c as usize]
+        if key.is_empty()
         {
-            result.push(c);
-            continue;
+            return;
         }
+        self.max_len = self.max_len.max(key.len());
         
-        let found = binary_search(pd.keys, c, 0);
-        // a result should be found
-        // if found.len() == 0
-        // {
-        //     result.push(c);
-        //     continue;
-        // }
-        
-        let s = find_value(&text.as_bytes()[i..], found);
-        if let Some(v) = s
+        let mut state = ROOT;
+        for (depth, b) in key.iter().enumerate()
         {
-            // append replacement
-            result.extend_from_slice(v.1.as_bytes());
-            // should skip bytes
-            if v.0.len() > 1
+            state = match self.goto(state, *b)
             {
-                // consume n + 1 elements
-                it.nth(v.0.len() - 2);
-            }
-            continue;
-        }
-        // false alarm
-        else
-        {
-            result.push(c);
+                Some(s) => s,
+                None =>
+                {
+                    let new = self.states.len();
+                    self.states.push(State::new(depth + 1));
+                    let next = &mut self.states[state].next;
+                    // not found so will be an error
+                    let at = next.binary_search_by_key(b, |t| t.0).unwrap_err();
+                    next.insert(at, (*b, new));
+                    new
+                }
+            };
         }
+        
+        // first key takes priority
+        self.states[state].key.get_or_insert(index);
     }
     
-    return result;
-}
-
-fn find_value<'a>(start: &[u8], mut sub_set: &'a [(String, String)]) -> Option<&'a (String, String)>
-{
-    // add extra zero so that keys which are at end can be found
-    for (i, b) in start.iter().skip(1).chain([&0]).enumerate()
+    /// Sets the failure transitions in breadth first order.
+    /// States at or after a key on their own path fail to `DEAD`,
+    /// as any match found after failing would start later than that key.
+    /// The same goes for states whose failure would pass such a key on another path
+    fn fill_fail(&mut self)
     {
-        // due to skipped
-        let i = i + 1;
-        let found = binary_search(sub_set, *b, i);
-        // end - 
-        if found.len() == 0
+        // state and whether it follows a key
+        let mut queue = VecDeque::new();
+        for (_, s) in self.states[ROOT].next.clone()
         {
-            // i is number of valid characters so far
-            for s in sub_set
+            let matched = self.states[s].key.is_some();
+            self.states[s].fail = if matched { DEAD } else { ROOT };
+            queue.push_back((s, matched));
+        }
+        
+        while let Some((state, matched)) = queue.pop_front()
+        {
+            for (b, next) in self.states[state].next.clone()
             {
-                if s.0.len() == i
+                let matched = matched || self.states[next].key.is_some();
+                queue.push_back((next, matched));
+                if matched
                 {
-                    return Some(s);
+                    self.states[next].fail = DEAD;
+                    continue;
                 }
+                
+                let mut fail = self.states[state].fail;
+                let fail = loop
+                {
+                    if fail == ROOT
+                    {
+                        break self.root[b as usize];
+                    }
+                    if let Some(s) = self.goto(fail, b)
+                    {
+                        break s;
+                    }
+                    fail = self.states[fail].fail;
+                    // passed a key on another path, which ends the search as in `next`
+                    if fail == DEAD
+                    {
+                        break DEAD;
+                    }
+                };
+                
+                self.states[next].fail = fail;
+                // keys ending here that start later
+                self.states[next].key = self.states[fail].key;
             }
-            // no keys were completed
-            return None;
         }
-        
-        sub_set = found;
     }
     
-    return None;
+    #[inline]
+    fn goto(&self, state: usize, b: u8) -> Option<usize>
+    {
+        let next = &self.states[state].next;
+        return next.binary_search_by_key(&b, |t| t.0).ok().map(|i| next[i].1);
+    }
+    #[inline]
+    fn next(&self, mut state: usize, b: u8) -> usize
+    {
+        loop
+        {
+            if state == ROOT
+            {
+                return self.root[b as usize];
+            }
+            if let Some(s) = self.goto(state, b)
+            {
+                return s;
+            }
+            state = self.states[state].fail;
+            if state == DEAD
+            {
+                return DEAD;
+            }
+        }
+    }
 }
-fn valid(value: &(String, String), c: u8, index: usize) -> bool
+
+impl State
 {
-    return value.0.as_bytes().get(index).map_or(false, |b| *b == c);
+    fn new(depth: usize) -> Self
+    {
+        return Self { next: Vec::new(), fail: DEAD, depth, key: None };
+    }
 }
-fn binary_search(keys: &[(String, String)], c: u8, index: usize) -> &[(String, String)]
+
+/// Number of bytes searched for a null byte when detecting binary content
+const BINARY_SNIFF_LEN: usize = 8000;
+
+/// Whether `content` should be copied as is rather than parsed,
+/// either having a null byte near the start or not being valid utf8
+pub fn is_binary(content: &[u8]) -> bool
 {
-    let start = keys.binary_search_by(|s|
+    let start = &content[..content.len().min(BINARY_SNIFF_LEN)];
+    if start.contains(&0)
     {
-        s.0.as_bytes().get(index).map_or(Ordering::Less, |b| b.cmp(&c))
-    });
-    if start.is_err()
+        return true;
+    }
+    
+    return std::str::from_utf8(content).is_err();
+}
+
+/// Number of bytes read at a time when streaming
+pub const STREAM_CAPACITY: usize = 64 * 1024;
+
+/// What is known about the contents of a file after reading it through once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileInfo
+{
+    /// same as `is_binary`
+    pub binary: bool,
+    /// whether the file may contain block directives
+    pub blocks: bool
+}
+
+/// Reads through `reader` to find the same as `is_binary` and whether there are any blocks,
+/// only holding `STREAM_CAPACITY` bytes at a time
+pub fn inspect<R>(mut reader: R) -> io::Result<FileInfo>
+    where R: Read
+{
+    let mut info = FileInfo { binary: false, blocks: false };
+    let mut buffer = vec![0; STREAM_CAPACITY];
+    // bytes kept from the end of the last read, of an incomplete utf8 character
+    let mut kept = 0;
+    let mut total = 0;
+    // so that block starts split between reads are found
+    let mut last = 0;
+    
+    loop
     {
-        return &[];
+        let n = reader.read(&mut buffer[kept..])?;
+        let end = kept + n;
+        let chunk = &buffer[..end];
+        
+        if total < BINARY_SNIFF_LEN && chunk[kept..].iter().take(BINARY_SNIFF_LEN - total).any(|b| *b == 0)
+        {
+            info.binary = true;
+            return Ok(info);
+        }
+        total += n;
+        
+        let start = BLOCK_START.as_bytes();
+        info.blocks |= chunk.windows(start.len()).any(|w| w == start) ||
+            (last == start[0] && chunk.first() == Some(&start[1]));
+        
+        let valid = match std::str::from_utf8(chunk)
+        {
+            Ok(_) => end,
+            // error_len is none when the chunk ends part way through a character
+            Err(e) if e.error_len().is_none() && n != 0 => e.valid_up_to(),
+            Err(_) =>
+            {
+                info.binary = true;
+                return Ok(info);
+            }
+        };
+        if n == 0
+        {
+            return Ok(info);
+        }
+        
+        last = chunk[end - 1];
+        buffer.copy_within(valid..end, 0);
+        kept = end - valid;
     }
-    let start = start.unwrap();
+}
+
+/// Same as `parse`, but reads from `reader` and writes to `writer` in sections of `capacity` bytes,
+/// so that large files are not held in memory. Keys split between reads are still replaced
+pub fn parse_stream<R, W>(mut reader: R, mut writer: W, pd: &ParserData, capacity: usize) -> io::Result<()>
+    where R: Read, W: Write
+{
+    let mut buffer: Vec<u8> = Vec::with_capacity(capacity + pd.max_len);
+    let mut result = Vec::with_capacity(capacity);
     
-    let mut last = start;
-    for (i, k) in keys.iter().enumerate().skip(start + 1)
+    loop
     {
-        if valid(k, c, index)
+        let start = buffer.len();
+        buffer.resize(start + capacity.max(1), 0);
+        let n = reader.read(&mut buffer[start..])?;
+        buffer.truncate(start + n);
+        
+        let eof = n == 0;
+        let used = parse_bytes(&buffer, pd, &mut result, eof);
+        writer.write_all(&result)?;
+        result.clear();
+        
+        if eof
         {
-            last = i;
-            continue;
+            return writer.flush();
         }
-        break;
+        // the bytes that could be the start of a key
+        buffer.drain(..used);
     }
-    let mut first = start;
-    for (i, k) in keys.iter().enumerate().rev().skip(keys.len() - start)
+}
+
+pub fn parse(text: &str, pd: &ParserData) -> Vec<u8>
+{
+    let mut result = Vec::with_capacity(text.len());
+    parse_bytes(text.as_bytes(), pd, &mut result, true);
+    return result;
+}
+
+/// Appends `text` with keys replaced to `result`, returning the number of bytes used.
+/// If not at the `end`, bytes are left that need more text after them to know whether they are a key
+fn parse_bytes(text: &[u8], pd: &ParserData, result: &mut Vec<u8>, end: bool) -> usize
+{
+    let mut i = 0;
+    while i < text.len()
     {
-        if valid(k, c, index)
+        // find the next match, which is complete once the search is dead
+        let mut state = ROOT;
+        // key index and end
+        let mut found: Option<(usize, usize)> = None;
+        let mut j = i;
+        while j < text.len()
         {
-            first = i;
-            continue;
+            state = pd.next(state, text[j]);
+            j += 1;
+            if state == DEAD
+            {
+                break;
+            }
+            if let Some(k) = pd.states[state].key
+            {
+                found = Some((k, j));
+            }
+        }
+        
+        // more text could give a different match
+        if !end && state != DEAD
+        {
+            // a key found so far never starts before the current state,
+            // which could still become a longer match
+            let used = j - pd.states[state].depth;
+            result.extend_from_slice(&text[i..used]);
+            return used;
+        }
+        
+        match found
+        {
+            Some((k, e)) =>
+            {
+                let (key, value) = &pd.keys[k];
+                result.extend_from_slice(&text[i..(e - key.len())]);
+                result.extend_from_slice(value.as_bytes());
+                i = e;
+            },
+            None =>
+            {
+                result.extend_from_slice(&text[i..]);
+                i = text.len();
+            }
         }
-        break;
     }
     
-    return &keys[first..(last + 1)];
+    return text.len();
 }
\ No newline at end of file
@@ -0,0 +1,187 @@
+use std::path::{Component, Path};
+
+/// A path pattern supporting `*`, `?`, `[...]` and `**` segments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob
+{
+    segments: Vec<String>
+}
+
+impl Glob
+{
+    pub fn new(pattern: &str) -> Self
+    {
+        let pattern = pattern.trim_start_matches("./");
+        let segments = pattern.split('/')
+            .filter(|s| !s.is_empty() && *s != ".")
+            .map(|s| s.to_string())
+            .collect();
+        
+        return Self { segments };
+    }
+    
+    /// Matches a `/` separated path relative to the root of the pattern
+    pub fn matches(&self, path: &str) -> bool
+    {
+        let parts: Vec<&str> = path.split('/')
+            .filter(|s| !s.is_empty() && *s != ".")
+            .collect();
+        
+        return match_segments(&self.segments, &parts);
+    }
+    
+    pub fn matches_path(&self, path: &Path) -> bool
+    {
+        return self.matches(&to_glob_path(path));
+    }
+}
+
+/// Converts a relative path into the `/` separated form used by globs
+pub fn to_glob_path(path: &Path) -> String
+{
+    let mut result = String::new();
+    
+    for c in path.components()
+    {
+        if let Component::Normal(n) = c
+        {
+            if !result.is_empty()
+            {
+                result.push('/');
+            }
+            result.push_str(&n.to_string_lossy());
+        }
+    }
+    
+    return result;
+}
+
+fn match_segments(pattern: &[String], path: &[&str]) -> bool
+{
+    let first = match pattern.first()
+    {
+        Some(f) => f,
+        None => return path.is_empty()
+    };
+    
+    if first == "**"
+    {
+        return (0..=path.len()).any(|i| match_segments(&pattern[1..], &path[i..]));
+    }
+    
+    return !path.is_empty() &&
+        match_segment(first.as_bytes(), path[0].as_bytes()) &&
+        match_segments(&pattern[1..], &path[1..]);
+}
+
+fn match_segment(pattern: &[u8], name: &[u8]) -> bool
+{
+    let (mut p, mut n) = (0, 0);
+    // position to return to on a failed match after a *
+    let mut star: Option<(usize, usize)> = None;
+    
+    while n < name.len()
+    {
+        if p < pattern.len()
+        {
+            match pattern[p]
+            {
+                b'*' =>
+                {
+                    star = Some((p, n));
+                    p += 1;
+                    continue;
+                },
+                b'?' =>
+                {
+                    p += 1;
+                    n += 1;
+                    continue;
+                },
+                b'[' =>
+                {
+                    if let Some((found, end)) = match_class(&pattern[p..], name[n])
+                    {
+                        if found
+                        {
+                            p += end;
+                            n += 1;
+                            continue;
+                        }
+                    }
+                    else if name[n] == b'['
+                    {
+                        p += 1;
+                        n += 1;
+                        continue;
+                    }
+                },
+                b'\\' if p + 1 < pattern.len() && pattern[p + 1] == name[n] =>
+                {
+                    p += 2;
+                    n += 1;
+                    continue;
+                },
+                c if c == name[n] =>
+                {
+                    p += 1;
+                    n += 1;
+                    continue;
+                },
+                _ => {}
+            }
+        }
+        
+        // backtrack to last *
+        match star
+        {
+            Some((sp, sn)) =>
+            {
+                star = Some((sp, sn + 1));
+                p = sp + 1;
+                n = sn + 1;
+            },
+            None => return false
+        }
+    }
+    
+    // only trailing *s left
+    return pattern[p..].iter().all(|c| *c == b'*');
+}
+
+/// Returns whether `c` is in the class and the length of the class, or `None` if the class is not closed
+fn match_class(pattern: &[u8], c: u8) -> Option<(bool, usize)>
+{
+    let mut i = 1;
+    let negate = matches!(pattern.get(i), Some(b'!') | Some(b'^'));
+    if negate
+    {
+        i += 1;
+    }
+    
+    let mut found = false;
+    let mut first = true;
+    while i < pattern.len()
+    {
+        let start = pattern[i];
+        if start == b']' && !first
+        {
+            return Some((found != negate, i + 1));
+        }
+        first = false;
+        
+        // range
+        if pattern.get(i + 1) == Some(&b'-') && pattern.get(i + 2).is_some_and(|e| *e != b']')
+        {
+            let end = pattern[i + 2];
+            found |= start <= c && c <= end;
+            i += 3;
+            continue;
+        }
+        
+        found |= start == c;
+        i += 1;
+    }
+    
+    return None;
+}
\ No newline at end of file
@@ -0,0 +1,167 @@
+use std::path::Path;
+
+use crate::{data::Config, error::{IntoProjUpError, ProjUpError}};
+
+use super::{to_glob_path, Glob};
+
+/// Name of the file in the root of a template listing paths that are not copied
+pub const IGNORE_FILE: &str = ".projupignore";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IgnoreRule
+{
+    glob: Glob,
+    /// for patterns ending in `/**`, which only match what is inside the folder rather than the folder itself
+    folder: Option<Glob>,
+    /// includes matching paths again
+    negate: bool,
+    dir_only: bool
+}
+
+/// Patterns of paths to ignore, using the same syntax as .gitignore files
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Ignore
+{
+    rules: Vec<IgnoreRule>
+}
+
+impl Ignore
+{
+    pub fn from_content(content: &str) -> Self
+    {
+        let mut rules = Vec::new();
+        
+        for line in content.lines()
+        {
+            let mut line = trim_end(line);
+            if line.is_empty() || line.starts_with('#')
+            {
+                continue;
+            }
+            
+            let negate = line.starts_with('!');
+            if negate
+            {
+                line = &line[1..];
+            }
+            // escaped start characters
+            if line.starts_with("\\#") || line.starts_with("\\!")
+            {
+                line = &line[1..];
+            }
+            
+            let dir_only = line.ends_with('/');
+            let line = line.trim_end_matches('/');
+            if line.is_empty()
+            {
+                continue;
+            }
+            
+            // patterns with a separator are relative to the root, otherwise they match at any depth
+            let pattern = if line.contains('/')
+            {
+                line.trim_start_matches('/').to_string()
+            }
+            else
+            {
+                format!("**/{line}")
+            };
+            let folder = pattern.strip_suffix("/**").map(Glob::new);
+            
+            rules.push(IgnoreRule { glob: Glob::new(&pattern), folder, negate, dir_only });
+        }
+        
+        return Self { rules };
+    }
+    
+    /// Reads the ignore file in `dir`, which is empty if there is no file
+    pub fn from_dir(dir: impl AsRef<Path>) -> std::io::Result<Self>
+    {
+        let path = dir.as_ref().join(IGNORE_FILE);
+        if !path.exists()
+        {
+            return Ok(Self::default());
+        }
+        
+        return Ok(Self::from_content(&std::fs::read_to_string(path)?));
+    }
+    
+    /// Whether `path` relative to the root is ignored, with later patterns taking priority
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool
+    {
+        let path = to_glob_path(path);
+        
+        for r in self.rules.iter().rev()
+        {
+            if r.dir_only && !is_dir
+            {
+                continue;
+            }
+            if r.glob.matches(&path) && !r.folder.as_ref().is_some_and(|f| f.matches(&path))
+            {
+                return !r.negate;
+            }
+        }
+        
+        return false;
+    }
+}
+
+/// Which files of a template are copied, and which are copied without substitution
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateFilter
+{
+    ignore: Ignore,
+    /// files whose conditions were not met
+    excluded: Vec<Glob>,
+    binary: Vec<Glob>
+}
+
+impl TemplateFilter
+{
+    /// Uses the ignore file of `template` and the `[files]` and `[binary]` sections of `config`
+    pub fn new(template: &Path, config: &Config) -> Result<Self, ProjUpError>
+    {
+        let ignore = Ignore::from_dir(template).projup(template.join(IGNORE_FILE))?;
+        let excluded = config.files.iter()
+            .filter(|f| !f.include)
+            .map(|f| Glob::new(&f.pattern))
+            .collect();
+        let binary = config.binary.iter()
+            .map(|b| Glob::new(b))
+            .collect();
+        
+        return Ok(Self { ignore, excluded, binary });
+    }
+    
+    /// Whether `path` relative to the template root is copied into the project
+    pub fn is_included(&self, path: &Path, is_dir: bool) -> bool
+    {
+        // the repository of templates added with git, and the files read by projup
+        if path == Path::new(".git") || path == Path::new(".projup") || path == Path::new(IGNORE_FILE)
+        {
+            return false;
+        }
+        
+        return !self.excluded.iter().any(|g| g.matches_path(path)) && !self.ignore.is_ignored(path, is_dir);
+    }
+    
+    /// Whether `path` relative to the template root is marked as binary
+    pub fn is_binary(&self, path: &Path) -> bool
+    {
+        return self.binary.iter().any(|g| g.matches_path(path));
+    }
+}
+
+/// Removes trailing spaces that are not escaped
+fn trim_end(line: &str) -> &str
+{
+    let trimmed = line.trim_end_matches([' ', '\r']);
+    if trimmed.ends_with('\\') && trimmed.len() < line.trim_end_matches('\r').len()
+    {
+        // keep the escaped space
+        return &line[..(trimmed.len() + 1)];
+    }
+    
+    return trimmed;
+}
\ No newline at end of file
@@ -1,12 +1,21 @@
 mod tokens;
 mod file_parser;
+mod blocks;
+mod glob;
+mod ignore;
+mod variables;
 pub mod traverse;
+pub mod ops;
 
 use std::{fs, path::{Path, PathBuf}};
 
 use directories::BaseDirs;
 pub use tokens::*;
 pub use file_parser::*;
+pub use blocks::*;
+pub use glob::*;
+pub use ignore::*;
+pub use variables::*;
 
 use crate::error::{IntoProjUpError, ProjUpError};
 
@@ -71,6 +80,12 @@ pub fn ensure_path<P>(path: Option<P>) -> std::io::Result<()>
 #[cfg(target_os = "windows")]
 pub fn absolute(path: impl AsRef<Path>) -> std::io::Result<PathBuf>
 {
+    // path will not have been created
+    if ops::is_dry_run() && !path.as_ref().exists()
+    {
+        return std::path::absolute(path);
+    }
+    
     let p = fs::canonicalize(path)?;
     if !p.starts_with("\\\\?\\")
     {
@@ -94,5 +109,11 @@ pub fn absolute(path: impl AsRef<Path>) -> std::io::Result<PathBuf>
 #[cfg(not(target_os = "windows"))]
 pub fn absolute(path: impl AsRef<Path>) -> std::io::Result<PathBuf>
 {
+    // path will not have been created
+    if ops::is_dry_run() && !path.as_ref().exists()
+    {
+        return std::path::absolute(path);
+    }
+    
     return Ok(fs::canonicalize(path)?);
 }
\ No newline at end of file
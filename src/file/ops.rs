@@ -0,0 +1,161 @@
+use std::{fs::{self, File, Metadata}, io, path::Path, sync::atomic::{AtomicBool, Ordering}};
+use log::info;
+
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// When set, the operations in this module are logged instead of being performed
+pub fn set_dry_run(dry_run: bool)
+{
+    DRY_RUN.store(dry_run, Ordering::Relaxed);
+}
+pub fn is_dry_run() -> bool
+{
+    return DRY_RUN.load(Ordering::Relaxed);
+}
+
+pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()>
+{
+    if is_dry_run()
+    {
+        info!("Would write {}", path.as_ref().display());
+        return Ok(());
+    }
+    
+    remove_link(&path)?;
+    return fs::write(path, contents);
+}
+/// Creates the file at `path` and passes it to `write`, so that the contents do not need to be in memory
+pub fn write_with<F>(path: impl AsRef<Path>, write: F) -> io::Result<()>
+    where F: FnOnce(File) -> io::Result<()>
+{
+    if is_dry_run()
+    {
+        info!("Would write {}", path.as_ref().display());
+        return Ok(());
+    }
+    
+    remove_link(&path)?;
+    return write(File::create(path)?);
+}
+pub fn copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()>
+{
+    if is_dry_run()
+    {
+        info!("Would write {}", to.as_ref().display());
+        return Ok(());
+    }
+    
+    remove_link(&to)?;
+    return fs::copy(from, to).map(|_| ());
+}
+/// Removes `path` if it is a symbolic link, so that it is replaced rather than its target being written to
+fn remove_link(path: impl AsRef<Path>) -> io::Result<()>
+{
+    if fs::symlink_metadata(&path).is_ok_and(|m| m.is_symlink())
+    {
+        return fs::remove_file(path);
+    }
+    
+    return Ok(());
+}
+/// Creates a symbolic link at `link` pointing to `target`, replacing any file already there.
+/// `dir` is whether the target is a directory, which is needed on windows
+pub fn symlink(target: impl AsRef<Path>, link: impl AsRef<Path>, dir: bool) -> io::Result<()>
+{
+    if is_dry_run()
+    {
+        info!("Would link {} to {}", link.as_ref().display(), target.as_ref().display());
+        return Ok(());
+    }
+    
+    if fs::symlink_metadata(&link).is_ok_and(|m| !m.is_dir())
+    {
+        fs::remove_file(&link)?;
+    }
+    
+    #[cfg(unix)]
+    {
+        let _ = dir;
+        return std::os::unix::fs::symlink(target, link);
+    }
+    #[cfg(windows)]
+    {
+        if dir
+        {
+            return std::os::windows::fs::symlink_dir(target, link);
+        }
+        return std::os::windows::fs::symlink_file(target, link);
+    }
+}
+/// Gives `path` the permissions of `from`, and its modification time if `times`.
+/// The owner can always write to `path`, so that it can be replaced by a later template or command
+pub fn copy_metadata(from: &Metadata, path: impl AsRef<Path>, times: bool) -> io::Result<()>
+{
+    // file will not have been written
+    if is_dry_run()
+    {
+        return Ok(());
+    }
+    
+    if times
+    {
+        File::options().write(true).open(&path)?.set_modified(from.modified()?)?;
+    }
+    
+    let mut permissions = from.permissions();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(permissions.mode() | 0o200);
+    }
+    #[cfg(windows)]
+    #[allow(clippy::permissions_set_readonly_false)]
+    {
+        permissions.set_readonly(false);
+    }
+    return fs::set_permissions(path, permissions);
+}
+/// Same as `write`, but logs the new contents of the file on a dry run
+pub fn write_registry(path: impl AsRef<Path>, contents: String) -> io::Result<()>
+{
+    if is_dry_run()
+    {
+        info!("Would update {} to:\n{}", path.as_ref().display(), contents);
+        return Ok(());
+    }
+    
+    return fs::write(path, contents);
+}
+pub fn create_dir_all(path: impl AsRef<Path>) -> io::Result<()>
+{
+    if is_dry_run()
+    {
+        if !path.as_ref().exists()
+        {
+            info!("Would create directory {}", path.as_ref().display());
+        }
+        return Ok(());
+    }
+    
+    return fs::create_dir_all(path);
+}
+pub fn remove_dir_all(path: impl AsRef<Path>) -> io::Result<()>
+{
+    if is_dry_run()
+    {
+        info!("Would delete directory {}", path.as_ref().display());
+        return Ok(());
+    }
+    
+    return fs::remove_dir_all(path);
+}
+pub fn rename(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()>
+{
+    if is_dry_run()
+    {
+        info!("Would rename {} to {}", from.as_ref().display(), to.as_ref().display());
+        return Ok(());
+    }
+    
+    return fs::rename(from, to);
+}
\ No newline at end of file
@@ -1,7 +1,11 @@
-use std::{fs::{self, DirEntry}, path::{Path, PathBuf}};
+use std::{collections::HashMap, ffi::{OsStr, OsString}, fs::{self, DirEntry}, path::{Component, Path, PathBuf}, sync::{atomic::{AtomicUsize, Ordering}, Mutex}, thread};
+
+use log::info;
 
 use crate::error::{IntoProjUpError, ProjUpError};
 
+use super::ops;
+
 pub fn by_folder<F>(root: &Path, mut f: F) -> Result<(), ProjUpError>
     where F: FnMut(DirEntry) -> Result<(), ProjUpError>
 {
@@ -35,6 +39,12 @@ pub fn try_move<P, Q>(from: P, to: Q) -> std::io::Result<()>
     where P: AsRef<Path>,
         Q: AsRef<Path>
 {
+    if ops::is_dry_run()
+    {
+        info!("Would move {} to {}", from.as_ref().display(), to.as_ref().display());
+        return Ok(());
+    }
+    
     // try rename
     match fs::rename(&from, &to)
     {
@@ -76,26 +86,179 @@ pub fn copy_dir_all(from: impl AsRef<Path>, to: impl AsRef<Path>) -> std::io::Re
     });
 }
 
+#[inline]
 pub fn copy_dir_all_func<F>(from: impl AsRef<Path>, to: impl AsRef<Path>, copy: &F) -> Result<(), ProjUpError>
-    where F: Fn(PathBuf, PathBuf) -> Result<(), ProjUpError>
+    where F: Fn(PathBuf, PathBuf) -> Result<(), ProjUpError> + Sync
 {
-    fs::create_dir_all(&to).projup(&to)?;
+    return copy_dir_filter_func(from, to, &|_, _| true, copy);
+}
+
+/// Only copies entries where `filter` returns true for their path relative to `from`, and whether they are a directory.
+/// Directories are created in order first, then files are copied on multiple threads.
+/// If any copies fail, the error of the first file in path order is returned
+#[inline]
+pub fn copy_dir_filter_func<G, F>(from: impl AsRef<Path>, to: impl AsRef<Path>, filter: &G, copy: &F) -> Result<(), ProjUpError>
+    where G: Fn(&Path, bool) -> bool,
+        F: Fn(PathBuf, PathBuf) -> Result<(), ProjUpError> + Sync
+{
+    return copy_dir_rename_func(from, to, filter, &|n| n.to_os_string(), copy);
+}
+
+/// Same as `copy_dir_filter_func`, but directories are created with the name returned by `rename`.
+/// Fails if two directories in the same folder are renamed to the same name, or a name is not valid for `check_name`
+pub fn copy_dir_rename_func<G, R, F>(from: impl AsRef<Path>, to: impl AsRef<Path>, filter: &G, rename: &R, copy: &F) -> Result<(), ProjUpError>
+    where G: Fn(&Path, bool) -> bool,
+        R: Fn(&OsStr) -> OsString,
+        F: Fn(PathBuf, PathBuf) -> Result<(), ProjUpError> + Sync
+{
+    let mut files = Vec::new();
+    collect_dir_relative(from.as_ref(), to.as_ref(), Path::new(""), filter, rename, &mut files)?;
+    
+    let threads = thread::available_parallelism().map_or(1, |n| n.get()).min(files.len());
+    if threads <= 1
+    {
+        for (f, t) in files
+        {
+            copy(f, t)?;
+        }
+        return Ok(());
+    }
+    
+    let next = AtomicUsize::new(0);
+    // files after the first error do not need to be copied
+    let first_error = AtomicUsize::new(usize::MAX);
+    let files = &files;
     
-    let to = to.as_ref();
-    for entry in fs::read_dir(&from).projup(&from)?
+    let mut errors: Vec<(usize, ProjUpError)> = thread::scope(|s|
     {
-        let entry = entry.projup(&from)?;
-        let ty = entry.file_type().projup(&from)?;
-        let dst = to.join(entry.file_name());
+        let handles: Vec<_> = (0..threads).map(|_| s.spawn(||
+        {
+            let mut errors = Vec::new();
+            loop
+            {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= files.len() || i > first_error.load(Ordering::Relaxed)
+                {
+                    return errors;
+                }
+                
+                let (f, t) = &files[i];
+                if let Err(e) = copy(f.clone(), t.clone())
+                {
+                    first_error.fetch_min(i, Ordering::Relaxed);
+                    errors.push((i, e));
+                }
+            }
+        })).collect();
+        
+        // copy will not panic unless there is a bug
+        return handles.into_iter().flat_map(|h| h.join().unwrap()).collect();
+    });
+    
+    errors.sort_by_key(|e| e.0);
+    return match errors.into_iter().next()
+    {
+        Some((_, e)) => Err(e),
+        None => Ok(())
+    };
+}
+
+/// Creates the directories in `from` within `to`, adding the files to be copied to `files`
+fn collect_dir_relative<G, R>(from: &Path, to: &Path, relative: &Path, filter: &G, rename: &R, files: &mut Vec<(PathBuf, PathBuf)>) -> Result<(), ProjUpError>
+    where G: Fn(&Path, bool) -> bool,
+        R: Fn(&OsStr) -> OsString
+{
+    ops::create_dir_all(to).projup(to)?;
+    
+    let mut entries = fs::read_dir(from).projup(from)?
+        .collect::<Result<Vec<_>, _>>().projup(from)?;
+    // so that the order is the same on all systems
+    entries.sort_by_key(|e| e.file_name());
+    // the source of each created directory, to find directories renamed to the same name
+    let mut created: HashMap<OsString, PathBuf> = HashMap::new();
+    
+    for entry in entries
+    {
+        let ty = entry.file_type().projup(from)?;
+        let rel = relative.join(entry.file_name());
+        if !filter(&rel, ty.is_dir())
+        {
+            continue;
+        }
+        
         if ty.is_dir()
         {
-            copy_dir_all_func(entry.path(), dst, copy)?;
+            let name = rename(&entry.file_name());
+            check_name(&entry.path(), &name)?;
+            let dst = to.join(&name);
+            if let Some(other) = created.insert(name, entry.path())
+            {
+                return Err(ProjUpError::PathCollision(other, entry.path(), dst));
+            }
+            collect_dir_relative(&entry.path(), &dst, &rel, filter, rename, files)?;
         }
         else
         {
-            copy(entry.path(), dst)?;
+            files.push((entry.path(), to.join(entry.file_name())));
         }
     }
     
     return Ok(());
+}
+
+/// Fails if `name`, which the template path `from` was renamed to, is not a single name,
+/// e.g. if it contains a separator or is `..`, which would write outside of its folder
+pub fn check_name(from: &Path, name: &OsStr) -> Result<(), ProjUpError>
+{
+    let mut components = Path::new(name).components();
+    if matches!((components.next(), components.next()), (Some(Component::Normal(n)), None) if n == name)
+    {
+        return Ok(());
+    }
+    
+    return Err(ProjUpError::InvalidRenderedName(from.to_path_buf(), name.to_string_lossy().to_string()));
+}
+
+/// The template path that each output path was rendered from, to find template paths that are written to the same place
+#[derive(Default)]
+pub struct RenderedPaths
+{
+    paths: Mutex<HashMap<PathBuf, PathBuf>>
+}
+
+impl RenderedPaths
+{
+    /// Fails if `to` was already rendered from another template path
+    pub fn insert(&self, from: &Path, to: &Path) -> Result<(), ProjUpError>
+    {
+        return match self.paths.lock().unwrap().insert(to.to_path_buf(), from.to_path_buf())
+        {
+            Some(other) => Err(ProjUpError::PathCollision(other, from.to_path_buf(), to.to_path_buf())),
+            None => Ok(())
+        };
+    }
+}
+
+/// `path` with ".new" added to its name, or ".new.1", ".new.2" and so on if that already exists,
+/// so that earlier new files are kept. Existing paths that `reuse` accepts are written over
+pub fn new_path<F>(path: &Path, reuse: F) -> PathBuf
+    where F: Fn(&Path) -> bool
+{
+    let mut n = 0;
+    loop
+    {
+        let mut name = path.file_name().unwrap_or_default().to_os_string();
+        name.push(".new");
+        if n != 0
+        {
+            name.push(format!(".{}", n));
+        }
+        
+        let new = path.with_file_name(name);
+        if !new.exists() || reuse(&new)
+        {
+            return new;
+        }
+        n += 1;
+    }
 }
\ No newline at end of file
@@ -0,0 +1,104 @@
+use std::{fs::{self, File}, path::Path};
+
+use crate::{data::{ConfigError, VariableMap}, error::{IntoProjUpError, ProjUpError}, invalid_template_file};
+
+use super::{block_variables, inspect, read_variable, split_name_loop, TemplateFilter};
+
+/// Replaces `$var` and `$var:"format"` references within `text` with their values from `vars`,
+/// leaving any `$` that does not start a reference untouched
+pub fn replace_variables<T>(text: &str, vars: &mut T) -> Result<String, ConfigError>
+    where T: VariableMap
+{
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$')
+    {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        
+        match read_variable(rest)
+        {
+            Some((n, f, r)) =>
+            {
+                // the text is a single line
+                result.push_str(&vars.map(1, n, f)?);
+                rest = r;
+            },
+            None =>
+            {
+                result.push('$');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    
+    return Ok(result);
+}
+
+/// Adds the variables referenced by the blocks and name loops of the files in `dir` to `used`,
+/// where `dir` is within `template`. Only files that `filter` includes as text are read
+pub fn file_variables(template: &Path, dir: &Path, filter: &TemplateFilter, used: &mut Vec<String>) -> Result<(), ProjUpError>
+{
+    for entry in fs::read_dir(dir).projup(dir)?
+    {
+        let entry = entry.projup(dir)?;
+        let path = entry.path();
+        let file_type = entry.file_type().projup(&path)?;
+        // path will always be within template
+        let rel = path.strip_prefix(template).unwrap_or(&path);
+        if !filter.is_included(rel, file_type.is_dir())
+        {
+            continue;
+        }
+        if file_type.is_dir()
+        {
+            file_variables(template, &path, filter, used)?;
+            continue;
+        }
+        if !file_type.is_file()
+        {
+            continue;
+        }
+        
+        let mut found = Vec::new();
+        let name = entry.file_name();
+        let name = name.to_str().unwrap_or_default();
+        let mut items = Vec::new();
+        match split_name_loop(name)
+        {
+            Some(Ok((l, _))) =>
+            {
+                found.push(l.variable.clone());
+                items.extend(l.scope(0, String::new()).map(|(n, _)| n));
+            },
+            Some(Err(_)) => return invalid_template_file!(path, ConfigError::InvalidFileLoop(name.to_string())),
+            None => {}
+        }
+        
+        // only text files with blocks need to be read
+        if !filter.is_binary(rel)
+        {
+            let info = inspect(File::open(&path).projup(&path)?).projup(&path)?;
+            if !info.binary && info.blocks
+            {
+                let text = fs::read_to_string(&path).projup(&path)?;
+                match block_variables(&text)
+                {
+                    Ok(v) => found.extend(v.into_iter().filter(|v| !items.contains(v))),
+                    Err(e) => return invalid_template_file!(path, e)
+                }
+            }
+        }
+        
+        for v in found
+        {
+            if !used.contains(&v)
+            {
+                used.push(v);
+            }
+        }
+    }
+    
+    return Ok(());
+}
\ No newline at end of file
@@ -1,6 +1,7 @@
-use std::{path::Path, process::Command};
+use std::{ffi::OsString, path::{Component, Path}, process::Command};
 
-use projup::error::{IntoProjUpError, ProjUpError};
+use log::info;
+use projup::{error::{IntoProjUpError, ProjUpError}, file::ops};
 
 pub enum GitOperation<'a>
 {
@@ -13,7 +14,10 @@ pub enum GitOperation<'a>
     },
     SubmoduleAdd{
         url: &'a str,
-        path: &'a Path
+        path: &'a Path,
+        branch: Option<&'a str>,
+        depth: Option<usize>,
+        name: Option<&'a str>
     },
     RemoteAdd{
         name: &'a str,
@@ -25,7 +29,41 @@ pub enum GitOperation<'a>
     },
     Clone{
         url: &'a Path,
-        path: Option<&'a Path>
+        path: Option<&'a Path>,
+        /// tag or branch to check out
+        reference: Option<&'a str>,
+        depth: Option<usize>
+    },
+    Pull,
+    /// Stages all changes
+    Add,
+    Commit{
+        message: &'a str
+    },
+    Fetch{
+        /// name or url of the remote
+        remote: &'a str,
+        reference: &'a str,
+        depth: Option<usize>
+    },
+    /// Checks out `reference` with a detached head
+    Checkout{
+        reference: &'a str
+    },
+    /// Writes and stages the files of `reference` under the folder `prefix`
+    ReadTree{
+        prefix: &'a Path,
+        reference: &'a str
+    },
+    /// Removes the files in `path` from the index, leaving them in the working tree
+    Unstage{
+        path: &'a Path
+    },
+    /// Merges `reference` into the folder `prefix` as one squashed commit,
+    /// so that it can be updated with git subtree pull
+    SubtreeAdd{
+        prefix: &'a Path,
+        reference: &'a str
     }
 }
 
@@ -33,7 +71,7 @@ pub fn run<P>(opertaion: GitOperation, directory: P) -> Result<(), ProjUpError>
     where P: AsRef<Path>
 {
     let mut git = Command::new("git");
-    git.current_dir(directory);
+    git.current_dir(&directory);
     match opertaion
     {
         GitOperation::Init { bare } =>
@@ -56,10 +94,25 @@ pub fn run<P>(opertaion: GitOperation, directory: P) -> Result<(), ProjUpError>
             }
             git.arg(remote);
         },
-        GitOperation::SubmoduleAdd { url, path } =>
+        GitOperation::SubmoduleAdd { url, path, branch, depth, name } =>
         {
             git.arg("submodule");
             git.arg("add");
+            if let Some(b) = branch
+            {
+                git.arg("--branch");
+                git.arg(b);
+            }
+            if let Some(d) = depth
+            {
+                git.arg("--depth");
+                git.arg(d.to_string());
+            }
+            if let Some(n) = name
+            {
+                git.arg("--name");
+                git.arg(n);
+            }
             git.arg(url);
             git.arg(path);
         },
@@ -77,24 +130,153 @@ pub fn run<P>(opertaion: GitOperation, directory: P) -> Result<(), ProjUpError>
             git.arg(name);
             git.arg(url);
         },
-        GitOperation::Clone { url, path } =>
+        GitOperation::Clone { url, path, reference, depth } =>
         {
             git.arg("clone");
             git.arg("--recurse-submodules");
             git.arg("-j8");
+            if let Some(r) = reference
+            {
+                git.arg("--branch");
+                git.arg(r);
+            }
+            if let Some(d) = depth
+            {
+                git.arg("--depth");
+                git.arg(d.to_string());
+            }
             git.arg(url);
             if let Some(p) = path
             {
                 git.arg(p);
             }
+        },
+        GitOperation::Pull =>
+        {
+            git.arg("pull");
+            git.arg("--ff-only");
+            git.arg("--recurse-submodules");
+        },
+        GitOperation::Add =>
+        {
+            git.arg("add");
+            git.arg("--all");
+        },
+        GitOperation::Commit { message } =>
+        {
+            git.arg("commit");
+            // projects without a template have nothing to commit
+            git.arg("--allow-empty");
+            git.arg("-m");
+            git.arg(message);
+        },
+        GitOperation::Fetch { remote, reference, depth } =>
+        {
+            git.arg("fetch");
+            if let Some(d) = depth
+            {
+                git.arg("--depth");
+                git.arg(d.to_string());
+            }
+            git.arg(remote);
+            git.arg(reference);
+        },
+        GitOperation::Checkout { reference } =>
+        {
+            git.arg("checkout");
+            git.arg("--recurse-submodules");
+            git.arg("--detach");
+            git.arg(reference);
+        },
+        GitOperation::ReadTree { prefix, reference } =>
+        {
+            git.arg("read-tree");
+            // prefix is a path in the index, so cannot contain . and must end in a separator
+            let mut arg = OsString::from("--prefix=");
+            for c in prefix.components().filter(|c| *c != Component::CurDir)
+            {
+                arg.push(c);
+                arg.push("/");
+            }
+            git.arg(arg);
+            git.arg("-u");
+            git.arg(reference);
+        },
+        GitOperation::Unstage { path } =>
+        {
+            git.arg("rm");
+            git.arg("-r");
+            git.arg("--quiet");
+            git.arg("--cached");
+            git.arg("--");
+            git.arg(path);
+        },
+        GitOperation::SubtreeAdd { prefix, reference } =>
+        {
+            git.arg("subtree");
+            git.arg("add");
+            // prefix is a path in the repository, so cannot contain .
+            let mut arg = OsString::from("--prefix=");
+            for (i, c) in prefix.components().filter(|c| *c != Component::CurDir).enumerate()
+            {
+                if i != 0
+                {
+                    arg.push("/");
+                }
+                arg.push(c);
+            }
+            git.arg(arg);
+            git.arg("--squash");
+            git.arg(reference);
         }
     }
+    if ops::is_dry_run()
+    {
+        let args: Vec<_> = git.get_args().map(|a| a.to_string_lossy()).collect();
+        info!("Would run \"git {}\" in {}", args.join(" "), directory.as_ref().display());
+        return Ok(());
+    }
+    
     let out = git.output().projup("")?;
     if out.status.success()
     {
         return Ok(());
     }
     
+    let str = String::from_utf8(out.stderr).unwrap_or("".to_string());
+    return Err(ProjUpError::GitError(str));
+}
+
+/// Returns the hash of the commit checked out in `directory`
+pub fn head<P>(directory: P) -> Result<String, ProjUpError>
+    where P: AsRef<Path>
+{
+    let out = Command::new("git")
+        .current_dir(&directory)
+        .args(["rev-parse", "HEAD"])
+        .output().projup(&directory)?;
+    if out.status.success()
+    {
+        return Ok(String::from_utf8_lossy(&out.stdout).trim().to_string());
+    }
+    
+    let str = String::from_utf8(out.stderr).unwrap_or("".to_string());
+    return Err(ProjUpError::GitError(str));
+}
+
+/// Whether `directory` has no uncommitted changes, including untracked files
+pub fn is_clean<P>(directory: P) -> Result<bool, ProjUpError>
+    where P: AsRef<Path>
+{
+    let out = Command::new("git")
+        .current_dir(&directory)
+        .args(["status", "--porcelain"])
+        .output().projup(&directory)?;
+    if out.status.success()
+    {
+        return Ok(out.stdout.is_empty());
+    }
+    
     let str = String::from_utf8(out.stderr).unwrap_or("".to_string());
     return Err(ProjUpError::GitError(str));
 }
\ No newline at end of file
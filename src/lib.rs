@@ -5,5 +5,7 @@ pub mod error;
 pub const VAR_NAME: &str = "name";
 pub const VAR_FILE_NAMES: &str = "file_names";
 pub const VAR_VERSION: &str = "version";
+pub const VAR_EXTENDS: &str = "extends";
+pub const VAR_PRESERVE_TIMES: &str = "preserve_times";
 pub const VAR_DATE: &str = "date";
 pub const VAR_TIME: &str = "time";
\ No newline at end of file
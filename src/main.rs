@@ -5,35 +5,43 @@ mod actions;
 mod git;
 
 use clap::Parser;
-use cli::Cli;
-use projup::error::ProjUpError;
+use cli::{Cli, Command};
+use log::info;
+use projup::{error::ProjUpError, file::ops};
 
 fn main()
 {
     logger::init_logger();
     // log cli errors as the same
     let args = Cli::parse();
+    ops::set_dry_run(args.dry_run);
     
-    if let Err(e) = action(args)
+    if let Err(e) = action(args.command)
     {
         let code = e.discriminant();
         e.log();
         process::exit(code as i32);
     }
+    
+    if args.dry_run
+    {
+        info!("Dry run complete, no changes were made");
+    }
 }
 
-fn action(args: Cli) -> Result<(), ProjUpError>
+fn action(args: Command) -> Result<(), ProjUpError>
 {
     match args
     {
-        Cli::New(new_args) => return actions::new(new_args),
-        Cli::NewExisting(new_existing_args) => return actions::new_existing(new_existing_args),
-        Cli::Move(move_args) => return actions::r#move(move_args),
-        Cli::Remove(remove_args) => return actions::remove(remove_args),
-        Cli::Backup(backup_args) => return actions::backup(backup_args),
-        Cli::Templates(template_args) => return actions::templates(template_args),
-        Cli::Config(config_args) => return actions::config(config_args),
-        Cli::Ls => return actions::ls(),
-        Cli::Clone(clone_args) => return actions::clone(clone_args)
+        Command::New(new_args) => return actions::new(new_args),
+        Command::NewExisting(new_existing_args) => return actions::new_existing(new_existing_args),
+        Command::Apply(apply_args) => return actions::apply(apply_args),
+        Command::Move(move_args) => return actions::r#move(move_args),
+        Command::Remove(remove_args) => return actions::remove(remove_args),
+        Command::Backup(backup_args) => return actions::backup(backup_args),
+        Command::Templates(template_args) => return actions::templates(template_args),
+        Command::Config(config_args) => return actions::config(config_args),
+        Command::Ls => return actions::ls(),
+        Command::Clone(clone_args) => return actions::clone(clone_args)
     }
 }
\ No newline at end of file
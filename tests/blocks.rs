@@ -0,0 +1,181 @@
+use projup::{data::{ConfigArgs, ConfigError}, file::{self, Condition, Loop}};
+
+#[test]
+fn condition_parse()
+{
+    let c = Condition::parse("$with_ci");
+    assert_eq!(c, Some(Condition { negate: false, variable: "with_ci".to_string(), format: None, value: None }));
+    
+    let c = Condition::parse("!$with_ci");
+    assert_eq!(c, Some(Condition { negate: true, variable: "with_ci".to_string(), format: None, value: None }));
+    
+    let c = Condition::parse("$name:\"snake\" == my_proj");
+    assert_eq!(c, Some(Condition {
+        negate: false,
+        variable: "name".to_string(),
+        format: Some("snake".to_string()),
+        value: Some("my_proj".to_string())
+    }));
+    
+    let c = Condition::parse("$kind != \"a \\\"b\"");
+    assert_eq!(c, Some(Condition { negate: true, variable: "kind".to_string(), format: None, value: Some("a \"b".to_string()) }));
+    
+    assert_eq!(Condition::parse("with_ci"), None);
+    assert_eq!(Condition::parse("!$kind == a"), None);
+    assert_eq!(Condition::parse("$kind = a"), None);
+}
+
+#[test]
+fn blocks_evaluate()
+{
+    let source = "start
+$[if $with_ci]
+ci
+$[else]
+no ci
+$[end]
+  $[if $kind == lib]
+lib
+  $[elif $kind == bin]
+bin
+    $[if !$with_ci]
+never
+    $[end]
+  $[else]
+other
+  $[end]
+end";
+    
+    let mut args = ConfigArgs::new("test");
+    args.map.insert("with_ci", "true");
+    args.map.insert("kind", "bin");
+    
+    let r = file::parse_blocks(source, &mut args);
+    assert_eq!(r.as_deref(), Ok("start\nci\nbin\nend"));
+    
+    args.map.insert("with_ci", "false");
+    args.map.insert("kind", "other");
+    
+    let r = file::parse_blocks(source, &mut args);
+    assert_eq!(r.as_deref(), Ok("start\nno ci\nother\nend"));
+}
+
+#[test]
+fn blocks_invalid()
+{
+    let mut args = ConfigArgs::new("test");
+    args.map.insert("a", "true");
+    
+    let r = file::parse_blocks("$[if $a]\nyes", &mut args);
+    assert_eq!(r, Err(ConfigError::UnclosedBlock(1)));
+    
+    let r = file::parse_blocks("yes\n$[end]", &mut args);
+    assert_eq!(r, Err(ConfigError::InvalidBlock(2)));
+    
+    let r = file::parse_blocks("$[if $a]\n$[else]\n$[else]\n$[end]", &mut args);
+    assert_eq!(r, Err(ConfigError::InvalidBlock(3)));
+    
+    let r = file::parse_blocks("$[if a]\n$[end]", &mut args);
+    assert_eq!(r, Err(ConfigError::InvalidBlock(1)));
+    
+    let r = file::parse_blocks("$[if $b]\n$[end]", &mut args);
+    assert_eq!(r, Err(ConfigError::UnknownVariable(1, "b".to_string())));
+    
+    // unused variables in inactive blocks are not needed
+    let r = file::parse_blocks("$[if !$a]\n$[if $b]\n$[end]\n$[end]\nok", &mut args);
+    assert_eq!(r.as_deref(), Ok("ok"));
+}
+#[test]
+fn loop_parse()
+{
+    let l = Loop::parse("member in $members");
+    assert_eq!(l, Some(Loop { item: "member".to_string(), variable: "members".to_string(), format: None }));
+    
+    let l = Loop::parse(" m in $members:\"snake\" ");
+    assert_eq!(l, Some(Loop { item: "m".to_string(), variable: "members".to_string(), format: Some("snake".to_string()) }));
+    
+    assert_eq!(Loop::parse("m in members"), None);
+    assert_eq!(Loop::parse("m of $members"), None);
+    assert_eq!(Loop::parse("$m in $members"), None);
+    assert_eq!(Loop::parse("m in $members extra"), None);
+    
+    let l = file::split_name_loop("$[for m in $members]$[m].rs");
+    assert_eq!(l, Some(Ok((Loop { item: "m".to_string(), variable: "members".to_string(), format: None }, "$[m].rs"))));
+    assert_eq!(file::split_name_loop("$[form].rs"), None);
+    assert_eq!(file::split_name_loop("main.rs"), None);
+    assert_eq!(file::split_name_loop("$[for m in members]$[m].rs"), Some(Err(())));
+}
+
+#[test]
+fn blocks_loop()
+{
+    let source = "[workspace]
+members = [
+$[for m in $members]
+    \"$[m]\", # $[m_index] $[m:\"pascal\"] $[other]
+$[end]
+]
+$[for m in $members]
+$[if $m == cli]
+$[for f in $features]
+$[m]-$[f]
+$[end]
+$[end]
+$[end]
+end";
+    
+    let mut args = ConfigArgs::new("test");
+    args.map.insert("members", "core, cli,web");
+    args.map.insert("features", "a,b");
+    
+    let r = file::parse_blocks(source, &mut args);
+    assert_eq!(r.as_deref(), Ok("[workspace]
+members = [
+    \"core\", # 0 Core $[other]
+    \"cli\", # 1 Cli $[other]
+    \"web\", # 2 Web $[other]
+]
+cli-a
+cli-b
+end"));
+    
+    args.map.insert("members", "");
+    let r = file::parse_blocks("$[for m in $members]\n$[m]\n$[end]\nend", &mut args);
+    assert_eq!(r.as_deref(), Ok("end"));
+    
+    // loop variables from a file name
+    let r = file::parse_blocks_scoped("mod $[m];", &mut args, vec![("m".to_string(), "core".to_string())]);
+    assert_eq!(r.as_deref(), Ok("mod core;"));
+    
+    let r = file::parse_blocks("$[for m in $missing]\n$[end]", &mut args);
+    assert_eq!(r, Err(ConfigError::UnknownVariable(1, "missing".to_string())));
+    
+    let r = file::parse_blocks("$[for m in $members]\n$[else]\n$[end]", &mut args);
+    assert_eq!(r, Err(ConfigError::InvalidBlock(2)));
+    
+    let r = file::parse_blocks("$[for m in $members]\n", &mut args);
+    assert_eq!(r, Err(ConfigError::UnclosedBlock(1)));
+}
+
+#[test]
+fn block_variables()
+{
+    let text = "$[if $with_ci]
+$[for m in $members]
+mod $[m];
+$[if $m_index == 0]
+$[elif !$verbose]
+$[end]
+$[end]
+$[elif $kind == bin]
+$[else]
+$[if $with_ci]
+$[end]
+$[end]
+$[m]";
+    let r = file::block_variables(text);
+    assert_eq!(r, Ok(vec!["with_ci".to_string(), "kind".to_string(), "members".to_string(), "verbose".to_string()]));
+    
+    assert_eq!(file::block_variables("no blocks, $name"), Ok(vec![]));
+    assert_eq!(file::block_variables("$[if $a]\n"), Err(ConfigError::UnclosedBlock(1)));
+}
\ No newline at end of file
@@ -1,4 +1,21 @@
-use projup::data::{Config, ConfigError, Version, ConfigArgs};
+use projup::data::{Config, ConfigError, Version, ConfigArgs, FileFilter, DefaultPart, Dependency, DependencyKind, Hook, HookFailure, VarDecl, VarType, VariableMap};
+
+fn config(name: &str) -> Config
+{
+    return Config {
+        name: name.to_string(),
+        file_names: false,
+        preserve_times: false,
+        version: Version::ONE,
+        extends: None,
+        keys: vec![],
+        deps: vec![],
+        files: vec![],
+        binary: vec![],
+        vars: vec![],
+        hooks: vec![]
+    };
+}
 
 #[test]
 fn config_from_content_valid()
@@ -21,11 +38,17 @@ fn config_from_content_valid()
     let should = Config {
         name: "hellow".to_string(),
         file_names: false,
+        preserve_times: false,
         version: Version::ONE,
+        extends: None,
         keys: vec![("this".to_string(), "that".to_string()),
             ("date".to_string(), now.format("%d/%m/%Y").to_string()),
             ("year".to_string(), now.format("%Y").to_string())],
-        deps: vec![("./path/b".to_string(), "https://test".to_string())]
+        deps: vec![Dependency::new("./path/b".to_string(), "https://test".to_string())],
+        files: vec![],
+        binary: vec![],
+        vars: vec![],
+        hooks: vec![]
     };
     assert_eq!(c, Ok(should));
 }
@@ -41,9 +64,15 @@ fn config_from_content_valid_version()
     let should = Config {
         name: "helਪlow".to_string(),
         file_names: true,
+        preserve_times: false,
         version: Version::new(1, 14, 1),
+        extends: None,
         keys: vec![],
-        deps: vec![]
+        deps: vec![],
+        files: vec![],
+        binary: vec![],
+        vars: vec![],
+        hooks: vec![]
     };
     assert_eq!(c, Ok(should));
 }
@@ -66,9 +95,15 @@ fn config_from_content_min()
     let should = Config {
         name: "hellow".to_string(),
         file_names: false,
+        preserve_times: false,
         version: Version::ONE,
+        extends: None,
         keys: vec![],
-        deps: vec![]
+        deps: vec![],
+        files: vec![],
+        binary: vec![],
+        vars: vec![],
+        hooks: vec![]
     };
     assert_eq!(c, Ok(should));
 }
@@ -156,4 +191,522 @@ fn config_from_content_invalid()
     
     let c = Config::from_content(content, Some(()));
     assert_eq!(c, Err(ConfigError::InvalidSyntax(3)));
+}
+
+#[test]
+fn config_from_content_files()
+{
+    let content = "[template]
+        name = \"hellow\"
+        
+        [files]
+        ci/** = $with_ci
+        docs = !$with_ci
+        \"src/*.rs\" = $kind == \"bin\"
+        lib.rs = $kind != bin";
+    
+    let mut args = ConfigArgs::new("test");
+    args.map.insert("with_ci", "true");
+    args.map.insert("kind", "bin");
+    
+    let c = Config::from_content(content, Some(args));
+    let should = Config {
+        name: "hellow".to_string(),
+        file_names: false,
+        preserve_times: false,
+        version: Version::ONE,
+        extends: None,
+        keys: vec![],
+        deps: vec![],
+        files: vec![FileFilter { pattern: "ci/**".to_string(), variable: "with_ci".to_string(), include: true },
+            FileFilter { pattern: "docs".to_string(), variable: "with_ci".to_string(), include: false },
+            FileFilter { pattern: "src/*.rs".to_string(), variable: "kind".to_string(), include: true },
+            FileFilter { pattern: "lib.rs".to_string(), variable: "kind".to_string(), include: false }],
+        binary: vec![],
+        vars: vec![],
+        hooks: vec![]
+    };
+    assert_eq!(c, Ok(should));
+    
+    
+    let content = "[template]
+        name = \"hellow\"
+        
+        [files]
+        ci/** = with_ci";
+    
+    let c = Config::from_content(content, Some(()));
+    assert_eq!(c, Err(ConfigError::InvalidCondition(5)));
+}
+
+#[test]
+fn config_from_content_binary()
+{
+    let content = "[template]
+        name = \"hellow\"
+        
+        [binary]
+        assets/**
+        \"*.png\"
+        $name.bin";
+    
+    let c = Config::from_content(content, Some(ConfigArgs::new("test")));
+    let should = Config {
+        name: "hellow".to_string(),
+        file_names: false,
+        preserve_times: false,
+        version: Version::ONE,
+        extends: None,
+        keys: vec![],
+        deps: vec![],
+        files: vec![],
+        binary: vec!["assets/**".to_string(), "*.png".to_string(), "test.bin".to_string()],
+        vars: vec![],
+        hooks: vec![]
+    };
+    assert_eq!(c, Ok(should));
+    
+    
+    let content = "[template]
+        name = \"hellow\"
+        
+        [binary]
+        a = b";
+    
+    let c = Config::from_content(content, Some(()));
+    assert_eq!(c, Err(ConfigError::InvalidSyntax(5)));
+}
+
+#[test]
+fn config_from_content_extends()
+{
+    let content = "[template]
+        name = \"hellow\"
+        extends = base";
+    
+    let c = Config::from_content::<()>(content, None);
+    assert_eq!(c.map(|c| c.extends), Ok(Some("base".to_string())));
+    
+    let content = "[template]
+        name = \"hellow\"
+        extends = base
+        extends = other";
+    
+    let c = Config::from_content::<()>(content, None);
+    assert_eq!(c, Err(ConfigError::DuplicateProperty("extends".to_string())));
+    
+    let content = "[template]
+        name = \"hellow\"
+        extends = $name";
+    
+    let c = Config::from_content::<()>(content, None);
+    assert_eq!(c, Err(ConfigError::InvalidSyntax(3)));
+}
+#[test]
+fn config_inherit()
+{
+    let mut child = config("child");
+    child.keys = vec![("a".to_string(), "child".to_string())];
+    child.deps = vec![Dependency::new("./deps/x".to_string(), "child".to_string())];
+    child.binary = vec!["*.png".to_string()];
+    
+    let mut parent = config("parent");
+    parent.file_names = true;
+    parent.keys = vec![("a".to_string(), "parent".to_string()), ("b".to_string(), "parent".to_string())];
+    parent.deps = vec![Dependency::new("./deps/x".to_string(), "parent".to_string()), Dependency::new("./deps/y".to_string(), "parent".to_string())];
+    parent.files = vec![FileFilter { pattern: "ci".to_string(), variable: "ci".to_string(), include: false }];
+    parent.binary = vec!["*.png".to_string(), "*.jpg".to_string()];
+    
+    child.inherit(parent);
+    
+    let mut should = config("child");
+    should.keys = vec![("a".to_string(), "child".to_string()), ("b".to_string(), "parent".to_string())];
+    should.deps = vec![Dependency::new("./deps/x".to_string(), "child".to_string()), Dependency::new("./deps/y".to_string(), "parent".to_string())];
+    should.files = vec![FileFilter { pattern: "ci".to_string(), variable: "ci".to_string(), include: false }];
+    should.binary = vec!["*.png".to_string(), "*.jpg".to_string()];
+    assert_eq!(child, should);
+}
+#[test]
+fn config_from_content_vars()
+{
+    let content = "[template]
+        name = \"hellow\"
+        
+        [vars]
+        license = MIT
+        @description = \"License of the project\"
+        @values = \"MIT,Apache-2.0\"
+        author
+        
+        [subs]
+        a = $license";
+    
+    let c = Config::from_content(content, Some(ConfigArgs::new("test")));
+    let mut should = config("hellow");
+    should.keys = vec![("a".to_string(), "MIT".to_string())];
+    should.vars = vec![VarDecl {
+            name: "license".to_string(),
+            default: Some(vec![DefaultPart::Text("MIT".to_string())]),
+            description: Some("License of the project".to_string()),
+            var_type: VarType::String,
+            values: vec!["MIT".to_string(), "Apache-2.0".to_string()],
+            pattern: None,
+            line: 5
+        },
+        VarDecl::new("author".to_string(), 8)];
+    assert_eq!(c, Ok(should));
+    
+    let mut args = ConfigArgs::new("test");
+    args.map.insert("license", "GPL");
+    let c = Config::from_content(content, Some(args));
+    assert_eq!(c, Err(ConfigError::DisallowedValue(5, "license".to_string(), "GPL".to_string())));
+    
+    let content = "[template]
+        name = \"hellow\"
+        
+        [vars]
+        @description = nothing";
+    
+    let c = Config::from_content(content, Some(()));
+    assert_eq!(c, Err(ConfigError::InvalidSyntax(5)));
+    
+    let content = "[template]
+        name = \"hellow\"
+        
+        [vars]
+        a = 1
+        @other = 2";
+    
+    let c = Config::from_content(content, Some(()));
+    assert_eq!(c, Err(ConfigError::UnknownProperty(6, "@other".to_string())));
+    
+    let content = "[template]
+        name = \"hellow\"
+        
+        [vars]
+        name = 1";
+    
+    let c = Config::from_content(content, Some(()));
+    assert_eq!(c, Err(ConfigError::InvalidSyntax(5)));
+}
+#[test]
+fn config_args_declare()
+{
+    let mut args = ConfigArgs::new("test");
+    let mut child = VarDecl::new("a".to_string(), 1);
+    child.default = Some(vec![DefaultPart::Text("child".to_string())]);
+    let mut parent = VarDecl::new("a".to_string(), 1);
+    parent.default = Some(vec![DefaultPart::Text("parent".to_string())]);
+    
+    assert_eq!(args.declare(&[child]), Ok(()));
+    assert_eq!(args.declare(&[parent, VarDecl::new("b".to_string(), 2)]), Ok(()));
+    
+    assert_eq!(args.map(0, "a", None), Ok("child".to_string()));
+    assert_eq!(args.map(0, "b", None), Err(ConfigError::UnknownVariable(0, "b".to_string())));
+    
+    args.map.insert("a", "given");
+    assert_eq!(args.map(0, "a", None), Ok("given".to_string()));
+}
+
+#[test]
+fn var_decl_types()
+{
+    let content = "[template]
+        name = \"hellow\"
+        
+        [vars]
+        double
+        @type = bool
+        count = 3
+        @type = int
+        kind = bin
+        @type = enum
+        @values = \"bin,lib\"
+        ident
+        @type = pattern
+        @pattern = \"[a-z_]+\"
+        version = 0.1
+        @type = semver";
+    
+    let c = Config::from_content(content, Some(ConfigArgs::new("test"))).unwrap();
+    let types: Vec<VarType> = c.vars.iter().map(|v| v.var_type).collect();
+    assert_eq!(types, vec![VarType::Bool, VarType::Int, VarType::Enum, VarType::Pattern, VarType::Semver]);
+    
+    let valid = [("double", "true"), ("count", "-12"), ("kind", "lib"), ("ident", "my_proj"), ("version", "1.2.3")];
+    let invalid = [("double", "ture"), ("count", "1.5"), ("ident", "my-proj"), ("version", "1.a")];
+    for (n, v) in valid
+    {
+        let d = c.vars.iter().find(|d| d.name == n).unwrap();
+        assert_eq!(d.validate(v), Ok(()));
+    }
+    for (n, v) in invalid
+    {
+        let d = c.vars.iter().find(|d| d.name == n).unwrap();
+        assert_eq!(d.validate(v), Err(ConfigError::InvalidType(d.line, n.to_string(), v.to_string(), d.var_type)));
+    }
+    let d = c.vars.iter().find(|d| d.name == "kind").unwrap();
+    assert_eq!(d.validate("dylib"), Err(ConfigError::DisallowedValue(9, "kind".to_string(), "dylib".to_string())));
+    
+    // given values are checked even if not used
+    let mut args = ConfigArgs::new("test");
+    args.map.insert("double", "ture");
+    let c = Config::from_content(content, Some(args));
+    assert_eq!(c, Err(ConfigError::InvalidType(5, "double".to_string(), "ture".to_string(), VarType::Bool)));
+    
+    let content = "[template]
+        name = \"hellow\"
+        
+        [vars]
+        count = many
+        @type = int";
+    
+    let c = Config::from_content(content, Some(()));
+    assert_eq!(c, Err(ConfigError::InvalidType(5, "count".to_string(), "many".to_string(), VarType::Int)));
+    
+    let content = "[template]
+        name = \"hellow\"
+        
+        [vars]
+        kind
+        @type = enum";
+    
+    let c = Config::from_content(content, Some(()));
+    assert_eq!(c, Err(ConfigError::InvalidSyntax(5)));
+    
+    let content = "[template]
+        name = \"hellow\"
+        
+        [vars]
+        kind
+        @type = float";
+    
+    let c = Config::from_content(content, Some(()));
+    assert_eq!(c, Err(ConfigError::InvalidSyntax(6)));
+}
+
+#[test]
+fn config_from_content_computed_vars()
+{
+    let content = "[template]
+        name = \"hellow\"
+        
+        [vars]
+        crate_name = $name:\"snake\"
+        bin_name = $crate_name-cli
+        
+        [subs]
+        a = $bin_name
+        
+        [deps]
+        deps = https://$crate_name";
+    
+    let c = Config::from_content(content, Some(ConfigArgs::new("MyProj"))).unwrap();
+    assert_eq!(c.keys, vec![("a".to_string(), "my_proj-cli".to_string())]);
+    assert_eq!(c.deps, vec![Dependency::new("deps".to_string(), "https://my_proj".to_string())]);
+    assert_eq!(c.vars[1].default_to_string(), Some("$crate_name-cli".to_string()));
+    assert_eq!(c.vars[1].default_literal(), None);
+    
+    // given values replace the default
+    let mut args = ConfigArgs::new("MyProj");
+    args.map.insert("crate_name", "other");
+    let c = Config::from_content(content, Some(args)).unwrap();
+    assert_eq!(c.keys, vec![("a".to_string(), "other-cli".to_string())]);
+    
+    let content = "[template]
+        name = \"hellow\"
+        
+        [vars]
+        a = $b
+        b = x$c
+        c = $a
+        
+        [subs]
+        a = $b";
+    
+    let c = Config::from_content(content, Some(ConfigArgs::new("test")));
+    assert_eq!(c, Err(ConfigError::CyclicVariable(6, "b".to_string())));
+    
+    let content = "[template]
+        name = \"hellow\"
+        
+        [vars]
+        a = $missing
+        
+        [subs]
+        a = $a";
+    
+    let c = Config::from_content(content, Some(ConfigArgs::new("test")));
+    assert_eq!(c, Err(ConfigError::UnknownVariable(5, "missing".to_string())));
+}
+
+#[test]
+fn config_from_content_preserve_times()
+{
+    let content = "[template]
+        name = \"hellow\"
+        preserve_times = true";
+    
+    let c = Config::from_content::<()>(content, None);
+    assert_eq!(c.map(|c| c.preserve_times), Ok(true));
+    
+    let content = "[template]
+        name = \"hellow\"
+        preserve_times = sometimes";
+    
+    let c = Config::from_content::<()>(content, None);
+    assert_eq!(c, Err(ConfigError::InvalidSyntax(3)));
+}
+
+#[test]
+fn config_from_content_hooks()
+{
+    let content = "[template]
+        name = \"hellow\"
+        [hooks]
+        \"cargo fetch\"
+        \"chmod +x \" $name \".sh\"
+        @on_error = warn
+        \"echo done\"
+        @on_error = ignore";
+    
+    let c = Config::from_content(content, Some(ConfigArgs::new("test")));
+    // values are quoted for the shell
+    let chmod = if cfg!(windows) { "chmod +x \"test\".sh" } else { "chmod +x 'test'.sh" };
+    assert_eq!(c.map(|c| c.hooks), Ok(vec![
+        Hook { command: "cargo fetch".to_string(), on_error: HookFailure::Fail },
+        Hook { command: chmod.to_string(), on_error: HookFailure::Warn },
+        Hook { command: "echo done".to_string(), on_error: HookFailure::Ignore }
+    ]));
+    
+    // hooks are not read without args
+    let c = Config::from_content::<()>(content, None);
+    assert_eq!(c.map(|c| c.hooks), Ok(vec![]));
+    
+    let content = "[template]
+        name = \"hellow\"
+        [hooks]
+        @on_error = warn";
+    let c = Config::from_content(content, Some(ConfigArgs::new("test")));
+    assert_eq!(c, Err(ConfigError::InvalidSyntax(4)));
+    
+    let content = "[template]
+        name = \"hellow\"
+        [hooks]
+        \"cargo fetch\"
+        @on_error = sometimes";
+    let c = Config::from_content(content, Some(ConfigArgs::new("test")));
+    assert_eq!(c, Err(ConfigError::InvalidSyntax(5)));
+    
+    // parent hooks are run first
+    let mut child = config("child");
+    child.hooks = vec![Hook { command: "b".to_string(), on_error: HookFailure::Fail }];
+    let mut parent = config("parent");
+    parent.hooks = vec![
+        Hook { command: "a".to_string(), on_error: HookFailure::Fail },
+        Hook { command: "b".to_string(), on_error: HookFailure::Warn }
+    ];
+    child.inherit(parent);
+    assert_eq!(child.hooks, vec![
+        Hook { command: "a".to_string(), on_error: HookFailure::Fail },
+        Hook { command: "b".to_string(), on_error: HookFailure::Fail }
+    ]);
+}
+
+
+#[cfg(unix)]
+#[test]
+fn config_hooks_quoted()
+{
+    let content = "[template]
+        name = \"hellow\"
+        [hooks]
+        \"printf %s \" $name \"-\" $value";
+    
+    // would run the second command, or split the arguments, if not quoted
+    let mut args = ConfigArgs::new("my app");
+    args.map.insert("value", "x; echo 'injected' $HOME");
+    let c = Config::from_content(content, Some(args)).unwrap();
+    assert_eq!(c.hooks[0].command, "printf %s 'my app'-'x; echo '\\''injected'\\'' $HOME'");
+    
+    let out = std::process::Command::new("sh").arg("-c").arg(&c.hooks[0].command).output().unwrap();
+    assert_eq!(String::from_utf8(out.stdout).unwrap(), "my app-x; echo 'injected' $HOME");
+}
+#[test]
+fn config_from_content_deps_attributes()
+{
+    let content = "[template]
+        name = \"hellow\"
+        [deps]
+        ./deps/a = https://a
+        @branch = $channel
+        @depth = 1
+        @name = alpha
+        ./deps/b = https://b";
+    
+    let mut args = ConfigArgs::new("test");
+    args.map.insert("channel", "stable");
+    let c = Config::from_content(content, Some(args));
+    
+    let mut a = Dependency::new("./deps/a".to_string(), "https://a".to_string());
+    a.branch = Some("stable".to_string());
+    a.depth = Some(1);
+    a.name = Some("alpha".to_string());
+    assert_eq!(c.map(|c| c.deps), Ok(vec![a, Dependency::new("./deps/b".to_string(), "https://b".to_string())]));
+    
+    let invalid = [
+        ("@branch = main", ConfigError::InvalidSyntax(4)),
+        ("./deps/a = https://a\n@depth = 0", ConfigError::InvalidSyntax(5)),
+        ("./deps/a = https://a\n@depth = shallow", ConfigError::InvalidSyntax(5)),
+        ("./deps/a = https://a\n@name = a\n@name = b", ConfigError::DuplicateProperty("@name".to_string())),
+        ("./deps/a = https://a\n@tag = v1", ConfigError::UnknownProperty(5, "@tag".to_string()))
+    ];
+    for (deps, error) in invalid
+    {
+        let content = format!("[template]\nname = hellow\n[deps]\n{deps}");
+        let c = Config::from_content(&content, Some(ConfigArgs::new("test")));
+        assert_eq!(c, Err(error), "{deps}");
+    }
+}
+
+#[test]
+fn config_from_content_deps_kinds()
+{
+    let content = "[template]
+        name = \"hellow\"
+        [deps]
+        ./deps/a = https://a
+        @kind = submodule
+        ./deps/b = https://b
+        @commit = 0123456789abcdef0123456789abcdef01234567
+        @kind = vendor
+        ./deps/c = https://c
+        @kind = subtree
+        @branch = v1.0";
+    
+    let config = Config::from_content(content, Some(ConfigArgs::new("test")));
+    
+    let a = Dependency::new("./deps/a".to_string(), "https://a".to_string());
+    let mut b = Dependency::new("./deps/b".to_string(), "https://b".to_string());
+    b.kind = DependencyKind::Vendor;
+    b.commit = Some("0123456789abcdef0123456789abcdef01234567".to_string());
+    let mut c = Dependency::new("./deps/c".to_string(), "https://c".to_string());
+    c.kind = DependencyKind::Subtree;
+    c.branch = Some("v1.0".to_string());
+    assert_eq!(config.map(|c| c.deps), Ok(vec![a, b, c]));
+    
+    let invalid = [
+        ("./deps/a = https://a\n@kind = copy", ConfigError::InvalidSyntax(5)),
+        ("./deps/a = https://a\n@kind = clone\n@kind = vendor", ConfigError::DuplicateProperty("@kind".to_string())),
+        ("./deps/a = https://a\n@kind = submodule\n@kind = clone", ConfigError::DuplicateProperty("@kind".to_string())),
+        ("./deps/a = https://a\n@kind = vendor\n@commit = 1a2b3c", ConfigError::InvalidSyntax(6)),
+        ("./deps/a = https://a\n@kind = vendor\n@commit = 0123456789abcdef0123456789abcdef0123456g", ConfigError::InvalidSyntax(6)),
+        ("./deps/a = https://a\n@commit = 0123456789abcdef0123456789abcdef01234567", ConfigError::InvalidDependencyProperty("./deps/a".to_string(), "@commit".to_string(), DependencyKind::Submodule)),
+        ("./deps/a = https://a\n@name = a\n@kind = clone", ConfigError::InvalidDependencyProperty("./deps/a".to_string(), "@name".to_string(), DependencyKind::Clone))
+    ];
+    for (deps, error) in invalid
+    {
+        let content = format!("[template]\nname = hellow\n[deps]\n{deps}");
+        let c = Config::from_content(&content, Some(ConfigArgs::new("test")));
+        assert_eq!(c, Err(error), "{deps}");
+    }
 }
\ No newline at end of file
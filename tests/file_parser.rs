@@ -40,4 +40,161 @@ fn string_replace_2()
     let replace = "beans are ok, i wear shoes";
     
     assert_eq!(r, Ok(replace));
+}
+#[test]
+fn binary_detection()
+{
+    assert!(!file::is_binary("Hellow yelloਪ\n".as_bytes()));
+    assert!(!file::is_binary(&[]));
+    assert!(file::is_binary(&[b'a', 0, b'b']));
+    assert!(file::is_binary(&[0x89, b'P', b'N', b'G']));
+    // invalid utf8 after the sniffed section
+    let mut late = vec![b'a'; 10000];
+    late.push(0xff);
+    assert!(file::is_binary(&late));
+}
+
+#[test]
+fn string_replace_stream()
+{
+    let source = "bean are ok, i wear beans, Hellow yelloਪ beanbeans";
+    
+    let mut keys = vec![("bean".to_string(), "beans".to_string()),
+        ("beans".to_string(), "shoes".to_string()),
+        ("yelloਪ".to_string(), "yellow".to_string()),
+        ("Hellow".to_string(), "Hello".to_string())];
+    keys.sort_by(|a, b| a.0.cmp(&b.0));
+    
+    let pd = ParserData::new(&keys[..]);
+    let whole = file::parse(source, &pd);
+    
+    // keys split between every possible read
+    for capacity in 1..(source.len() + 2)
+    {
+        let mut result = Vec::new();
+        file::parse_stream(source.as_bytes(), &mut result, &pd, capacity).unwrap();
+        assert_eq!(result, whole, "capacity {capacity}");
+    }
+}
+#[test]
+fn file_inspect()
+{
+    let info = file::inspect("Hellow yelloਪ\n".as_bytes()).unwrap();
+    assert_eq!(info, file::FileInfo { binary: false, blocks: false });
+    
+    let info = file::inspect([b'a', 0, b'b'].as_slice()).unwrap();
+    assert!(info.binary);
+    
+    // split between reads
+    let mut text = "a".repeat(file::STREAM_CAPACITY - 1);
+    text.push_str("$[if $a]\nਪ");
+    let info = file::inspect(text.as_bytes()).unwrap();
+    assert_eq!(info, file::FileInfo { binary: false, blocks: true });
+    
+    let mut text = "a".repeat(file::STREAM_CAPACITY - 1);
+    text.push('ਪ');
+    let info = file::inspect(text.as_bytes()).unwrap();
+    assert_eq!(info, file::FileInfo { binary: false, blocks: false });
+    
+    let mut late = vec![b'a'; file::STREAM_CAPACITY * 2];
+    late.push(0xff);
+    assert!(file::inspect(late.as_slice()).unwrap().binary);
+    
+    // incomplete character at the end
+    let text = "ਪ".as_bytes();
+    assert!(file::inspect(&text[..2]).unwrap().binary);
+}
+
+fn replace(source: &str, keys: &[(&str, &str)]) -> String
+{
+    let keys: Vec<(String, String)> = keys.iter().map(|(a, b)| (a.to_string(), b.to_string())).collect();
+    let pd = ParserData::new(&keys);
+    return String::from_utf8(file::parse(source, &pd)).unwrap();
+}
+/// Leftmost longest replacement by checking every key at every position
+fn replace_naive(source: &str, keys: &[(&str, &str)]) -> String
+{
+    let mut result = String::new();
+    let mut i = 0;
+    while i < source.len()
+    {
+        let found = keys.iter()
+            .filter(|(k, _)| !k.is_empty() && source[i..].starts_with(k))
+            .fold(None, |best: Option<&(&str, &str)>, k| match best
+            {
+                Some(b) if b.0.len() >= k.0.len() => Some(b),
+                _ => Some(k)
+            });
+        match found
+        {
+            Some((k, v)) =>
+            {
+                result.push_str(v);
+                i += k.len();
+            },
+            None =>
+            {
+                result.push_str(&source[i..(i + 1)]);
+                i += 1;
+            }
+        }
+    }
+    
+    return result;
+}
+#[test]
+fn string_replace_leftmost_longest()
+{
+    // a shorter key is used when a longer one fails part way
+    assert_eq!(replace("abcx abcd", &[("ab", "1"), ("abcd", "2")]), "1cx 2");
+    // a later key is used when an earlier longer one fails
+    assert_eq!(replace("abcx abcy", &[("abcd", "1"), ("b", "2"), ("bcx", "3")]), "a3 a2cy");
+    // the earliest match wins over a longer later one
+    assert_eq!(replace("abcd", &[("ab", "1"), ("bcd", "2")]), "1cd");
+    // empty keys do nothing
+    assert_eq!(replace("abc", &[("", "x"), ("b", "2")]), "a2c");
+    assert_eq!(replace("abc", &[]), "abc");
+    // first duplicate is used
+    assert_eq!(replace("abc", &[("b", "1"), ("b", "2")]), "a1c");
+    
+    let keys = [("a", "1"), ("aa", "2"), ("aab", "3"), ("ba", "4"), ("bab", "5"), ("abb", "6"), ("b", "")];
+    replace_all_sources(&keys, &['a', 'b'], 8);
+}
+
+#[test]
+fn string_replace_key_within_key()
+{
+    // keys inside other keys, but not at their end
+    assert_eq!(replace("[[name]] name", &[("name", "x"), ("[[name]]", "y")]), "y x");
+    assert_eq!(replace("[[nam [[name", &[("name", "x"), ("[[name]]", "y")]), "[[nam [[x");
+    assert_eq!(replace("abcx abcy", &[("b", "1"), ("abcx", "2")]), "2 a1cy");
+    
+    replace_all_sources(&[("b", "1"), ("abcx", "2")], &['a', 'b', 'c', 'x'], 6);
+    replace_all_sources(&[("name", "x"), ("[[name]]", "y")], &['[', ']', 'n', 'a', 'm', 'e'], 6);
+    replace_all_sources(&[("abcx", "1"), ("b", "2"), ("cy", "3")], &['a', 'b', 'c', 'x', 'y'], 6);
+}
+
+/// Checks the replacement of every string of the `alphabet` up to `max_len` against `replace_naive`,
+/// both when parsed at once and when streamed
+fn replace_all_sources(keys: &[(&str, &str)], alphabet: &[char], max_len: u32)
+{
+    let owned: Vec<(String, String)> = keys.iter().map(|(a, b)| (a.to_string(), b.to_string())).collect();
+    let pd = ParserData::new(&owned);
+    let size = alphabet.len();
+    for len in 0..=max_len
+    {
+        for n in 0..size.pow(len)
+        {
+            let source: String = (0..len).map(|i| alphabet[n / size.pow(i) % size]).collect();
+            let should = replace_naive(&source, keys);
+            assert_eq!(replace(&source, keys), should, "source {source}");
+            
+            for capacity in 1..4
+            {
+                let mut result = Vec::new();
+                file::parse_stream(source.as_bytes(), &mut result, &pd, capacity).unwrap();
+                assert_eq!(String::from_utf8(result).unwrap(), should, "source {source}, capacity {capacity}");
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,37 @@
+use std::path::Path;
+
+use projup::file::Glob;
+
+#[test]
+fn glob_matches()
+{
+    let g = Glob::new("src/*.rs");
+    assert!(g.matches("src/main.rs"));
+    assert!(g.matches("./src/.rs"));
+    assert!(!g.matches("src/main.rs/x"));
+    assert!(!g.matches("src/a/main.rs"));
+    assert!(!g.matches("main.rs"));
+    
+    let g = Glob::new("./ci/**");
+    assert!(g.matches("ci"));
+    assert!(g.matches("ci/workflows/build.yml"));
+    assert!(!g.matches("src/ci"));
+    
+    let g = Glob::new("**/test_?.[ch]");
+    assert!(g.matches("test_a.c"));
+    assert!(g.matches("a/b/test_1.h"));
+    assert!(!g.matches("a/b/test_12.h"));
+    assert!(!g.matches("a/b/test_1.o"));
+    
+    let g = Glob::new("*a*b*c");
+    assert!(g.matches("aabbcc"));
+    assert!(g.matches("abc"));
+    assert!(!g.matches("acb"));
+    
+    let g = Glob::new("[!a-c]x");
+    assert!(g.matches("dx"));
+    assert!(!g.matches("bx"));
+    
+    assert!(Glob::new("docs").matches_path(Path::new("docs")));
+    assert!(Glob::new("docs/*.md").matches_path(&Path::new("docs").join("a.md")));
+}
\ No newline at end of file
@@ -0,0 +1,51 @@
+use std::path::Path;
+
+use projup::file::Ignore;
+
+fn ignored(ignore: &Ignore, path: &str, is_dir: bool) -> bool
+{
+    return ignore.is_ignored(Path::new(path), is_dir);
+}
+
+#[test]
+fn ignore_patterns()
+{
+    let ignore = Ignore::from_content("# editor files
+*.swp
+.vscode/
+/docs
+build/output
+\\#notes
+trailing\\\x20
+
+logs/**
+!logs/keep.txt
+");
+    
+    assert!(ignored(&ignore, "a.swp", false));
+    assert!(ignored(&ignore, "src/b.swp", false));
+    assert!(!ignored(&ignore, "a.swp.txt", false));
+    
+    // only directories
+    assert!(ignored(&ignore, ".vscode", true));
+    assert!(ignored(&ignore, "src/.vscode", true));
+    assert!(!ignored(&ignore, ".vscode", false));
+    
+    // relative to the root
+    assert!(ignored(&ignore, "docs", true));
+    assert!(!ignored(&ignore, "src/docs", true));
+    assert!(ignored(&ignore, "build/output", false));
+    assert!(!ignored(&ignore, "src/build/output", false));
+    
+    assert!(ignored(&ignore, "#notes", false));
+    assert!(ignored(&ignore, "trailing ", false));
+    assert!(!ignored(&ignore, "trailing", false));
+    
+    // later patterns take priority
+    assert!(!ignored(&ignore, "logs", true));
+    assert!(ignored(&ignore, "logs/a.txt", false));
+    assert!(!ignored(&ignore, "logs/keep.txt", false));
+    
+    assert!(!ignored(&ignore, "src/main.rs", false));
+    assert!(!ignored(&Ignore::default(), "a.swp", false));
+}
\ No newline at end of file
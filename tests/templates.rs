@@ -0,0 +1,89 @@
+use std::{fs, path::{Path, PathBuf}, str::FromStr};
+use projup::{data::{Config, TemplateSource, Templates, Version, VersionReq}, error::ProjUpError};
+
+#[test]
+fn templates_content_sources()
+{
+    let t = Templates::from_content("location = \"/templates\"
+\"local\"
+[git]
+\"remote\" = \"https://example.com/remote.git\"
+\"tagged\" = \"/repos/tagged\"
+@ref = \"v1.2\"
+").unwrap();
+    
+    assert!(t.contains("local"));
+    assert!(t.contains("remote"));
+    assert_eq!(t.try_get_source("local"), None);
+    assert_eq!(t.try_get_source("remote"), Some(&TemplateSource { url: "https://example.com/remote.git".to_string(), reference: None }));
+    assert_eq!(t.try_get_source("tagged"), Some(&TemplateSource { url: "/repos/tagged".to_string(), reference: Some("v1.2".to_string()) }));
+    
+    // written back the same
+    let t = Templates::from_content(&t.to_content()).unwrap();
+    assert!(t.contains("local"));
+    assert_eq!(t.sources().count(), 2);
+    assert_eq!(t.try_get_source("tagged").and_then(|s| s.reference.as_deref()), Some("v1.2"));
+    
+    // attribute without a template
+    assert!(Templates::from_content("location = \"/templates\"\n[git]\n@ref = \"v1\"").is_err());
+    assert!(Templates::from_content("location = \"/templates\"\n\"a\" = \"url\"").is_err());
+}
+
+fn temp_dir(name: &str) -> PathBuf
+{
+    let dir = std::env::temp_dir().join(format!("projup-templates-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    return dir;
+}
+
+fn add_template(location: &Path, folder: &str, name: &str, version: &str)
+{
+    let dir = location.join(folder);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join(".projup"), format!("[template]\nname = {name}\nversion = {version}")).unwrap();
+}
+
+#[test]
+fn templates_versions()
+{
+    let location = temp_dir("versions");
+    add_template(&location, "base", "base", "1.0.0");
+    add_template(&location, "base@1.5.0", "base", "1.5.0");
+    // folders are renamed to match their template
+    add_template(&location, "misnamed", "base", "2.0.0");
+    add_template(&location, "other", "other", "0.1.0");
+    
+    let mut t = Templates::new(location.to_str().unwrap().to_string());
+    assert!(t.find_templates(false).is_ok());
+    assert!(t.contains("base@2.0.0"));
+    assert!(!t.contains("misnamed"));
+    assert!(location.join("base@2.0.0").is_dir());
+    
+    // highest matching version
+    let r = t.try_get_version("base", &VersionReq::ANY).unwrap();
+    assert_eq!(r, Some((location.join("base@2.0.0"), Version::from_str("2.0.0").unwrap())));
+    let r = t.try_get_version("base", &VersionReq::from_str("^1").unwrap()).unwrap();
+    assert_eq!(r, Some((location.join("base@1.5.0"), Version::from_str("1.5.0").unwrap())));
+    let r = t.try_get_version("base", &VersionReq::from_str("<1.5").unwrap()).unwrap();
+    assert_eq!(r.map(|(p, _)| p), Some(location.join("base")));
+    assert_eq!(t.try_get_version("base", &VersionReq::from_str(">=3").unwrap()).unwrap(), None);
+    assert_eq!(t.try_get_version("missing", &VersionReq::ANY).unwrap(), None);
+    
+    // names with requirements
+    assert_eq!(t.find("base").ok(), Some(location.join("base@2.0.0")));
+    assert_eq!(t.find("base@~1.0").ok(), Some(location.join("base")));
+    assert_eq!(t.find("other@0.1").ok(), Some(location.join("other")));
+    assert!(matches!(t.find("base@>=3"), Err(ProjUpError::NoMatchingVersion(n, r)) if n == "base" && r == ">=3"));
+    assert!(matches!(t.find("base@not a version"), Err(ProjUpError::InvalidVersionRequirement(r)) if r == "not a version"));
+    assert!(matches!(t.find("missing@1"), Err(ProjUpError::UnkownTemplate(n)) if n == "missing"));
+    
+    // the name is used if it is free, otherwise the name and version
+    let config = |name: &str, version: &str| Config::from_content::<()>(&format!("[template]\nname = {name}\nversion = {version}"), None).unwrap();
+    assert_eq!(t.template_folder(None, &config("new", "1.0.0")).ok(), Some(location.join("new")));
+    assert_eq!(t.template_folder(None, &config("base", "3.0.0")).ok(), Some(location.join("base@3.0.0")));
+    assert_eq!(t.template_folder(Some("base"), &config("base", "1.0.0")).ok(), Some(location.join("base")));
+    assert_eq!(t.template_folder(Some("base@1.5.0"), &config("base", "1.5.0")).ok(), Some(location.join("base@1.5.0")));
+    assert!(matches!(t.template_folder(None, &config("base", "1.5.0")), Err(ProjUpError::DuplicateTemplate(n)) if n == "base@1.5.0"));
+    
+    fs::remove_dir_all(location).unwrap();
+}
\ No newline at end of file
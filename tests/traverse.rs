@@ -0,0 +1,246 @@
+use std::{ffi::{OsStr, OsString}, fs, path::{Path, PathBuf}};
+use projup::{error::ProjUpError, file::traverse};
+
+fn temp_dir(name: &str) -> PathBuf
+{
+    let dir = std::env::temp_dir().join(format!("projup-traverse-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    return dir;
+}
+
+#[test]
+fn copy_dir_parallel()
+{
+    let from = temp_dir("from");
+    let to = temp_dir("to");
+    for i in 0..50
+    {
+        let dir = from.join(format!("dir{}", i % 5));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(format!("file{i}.txt")), i.to_string()).unwrap();
+    }
+    fs::create_dir_all(from.join("empty")).unwrap();
+    
+    let r = traverse::copy_dir_filter_func(&from, &to, &|rel, _| !rel.ends_with("file7.txt"), &|f, t|
+    {
+        fs::copy(f, t).unwrap();
+        return Ok(());
+    });
+    assert!(r.is_ok());
+    
+    for i in 0..50
+    {
+        let file = to.join(format!("dir{}", i % 5)).join(format!("file{i}.txt"));
+        if i == 7
+        {
+            assert!(!file.exists());
+            continue;
+        }
+        assert_eq!(fs::read_to_string(file).unwrap(), i.to_string());
+    }
+    assert!(to.join("empty").is_dir());
+    
+    // first error in path order is returned
+    let r = traverse::copy_dir_all_func(&from, &to, &|f, _|
+    {
+        let name = f.file_name().unwrap().to_str().unwrap();
+        if name.starts_with("file2") || name.starts_with("file4")
+        {
+            return Err(ProjUpError::UnkownTemplate(name.to_string()));
+        }
+        return Ok(());
+    });
+    match r
+    {
+        Err(ProjUpError::UnkownTemplate(n)) => assert_eq!(n, "file20.txt"),
+        _ => panic!("expected an error")
+    }
+    
+    fs::remove_dir_all(from).unwrap();
+    fs::remove_dir_all(to).unwrap();
+}
+
+#[test]
+fn copy_dir_rename()
+{
+    let from = temp_dir("rename-from");
+    let to = temp_dir("rename-to");
+    fs::create_dir_all(from.join("src").join("crate_name").join("inner")).unwrap();
+    fs::write(from.join("src").join("crate_name").join("inner").join("crate_name.txt"), "a").unwrap();
+    
+    let rename = |n: &OsStr| -> OsString
+    {
+        return n.to_string_lossy().replace("crate_name", "foo").into();
+    };
+    let r = traverse::copy_dir_rename_func(&from, &to, &|_, _| true, &rename, &|f, t|
+    {
+        fs::copy(f, t).unwrap();
+        return Ok(());
+    });
+    assert!(r.is_ok());
+    // only directories are renamed
+    assert!(to.join("src").join("foo").join("inner").join("crate_name.txt").is_file());
+    assert!(!to.join("src").join("crate_name").exists());
+    
+    // directories renamed to the same name
+    fs::create_dir_all(from.join("src").join("foo")).unwrap();
+    let r = traverse::copy_dir_rename_func(&from, &to, &|_, _| true, &rename, &|_, _| Ok(()));
+    match r
+    {
+        Err(ProjUpError::PathCollision(a, b, c)) =>
+        {
+            assert_eq!(a, from.join("src").join("crate_name"));
+            assert_eq!(b, from.join("src").join("foo"));
+            assert_eq!(c, to.join("src").join("foo"));
+        },
+        _ => panic!("expected a collision")
+    }
+    
+    fs::remove_dir_all(from).unwrap();
+    fs::remove_dir_all(to).unwrap();
+}
+
+#[test]
+fn rendered_names()
+{
+    let from = PathBuf::from("template").join("$name");
+    for name in ["a.txt", "..a", "a b"]
+    {
+        assert!(traverse::check_name(&from, OsStr::new(name)).is_ok(), "{name}");
+    }
+    for name in ["a/b", "../a", "..", ".", "", "a/"]
+    {
+        match traverse::check_name(&from, OsStr::new(name))
+        {
+            Err(ProjUpError::InvalidRenderedName(p, n)) => assert_eq!((p, n.as_str()), (from.clone(), name)),
+            _ => panic!("expected {name} to be invalid")
+        }
+    }
+    
+    // directories cannot be renamed into another folder
+    let dir_from = temp_dir("separator-from");
+    let dir_to = temp_dir("separator-to");
+    fs::create_dir_all(dir_from.join("crate_name")).unwrap();
+    let r = traverse::copy_dir_rename_func(&dir_from, &dir_to, &|_, _| true, &|n| n.to_string_lossy().replace("crate_name", "../up").into(), &|_, _| Ok(()));
+    assert!(matches!(r, Err(ProjUpError::InvalidRenderedName(p, n)) if p == dir_from.join("crate_name") && n == "../up"));
+    assert!(!dir_to.join("..").join("up").exists());
+    
+    // files rendered to the same path
+    let rendered = traverse::RenderedPaths::default();
+    let out = PathBuf::from("project").join("lib.rs");
+    assert!(rendered.insert(Path::new("template/crate_name.rs"), &out).is_ok());
+    assert!(rendered.insert(Path::new("template/main.rs"), Path::new("project/main.rs")).is_ok());
+    match rendered.insert(Path::new("template/lib.rs"), &out)
+    {
+        Err(ProjUpError::PathCollision(a, b, c)) =>
+        {
+            assert_eq!(a, PathBuf::from("template/crate_name.rs"));
+            assert_eq!(b, PathBuf::from("template/lib.rs"));
+            assert_eq!(c, out);
+        },
+        _ => panic!("expected a collision")
+    }
+    
+    fs::remove_dir_all(dir_from).unwrap();
+    let _ = fs::remove_dir_all(dir_to);
+}
+
+#[cfg(unix)]
+#[test]
+fn copy_metadata_permissions_times()
+{
+    use std::{fs::File, os::unix::fs::PermissionsExt, time::{Duration, SystemTime}};
+    use projup::file::ops;
+    
+    let dir = temp_dir("metadata");
+    fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("script.sh");
+    let locked = dir.join("locked.txt");
+    fs::write(&script, "echo").unwrap();
+    fs::write(&locked, "locked").unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    fs::set_permissions(&locked, fs::Permissions::from_mode(0o444)).unwrap();
+    let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    File::options().write(true).open(&script).unwrap().set_modified(time).unwrap();
+    
+    // mode bits and times are kept
+    let out = dir.join("script_out.sh");
+    fs::write(&out, "echo").unwrap();
+    ops::copy_metadata(&fs::metadata(&script).unwrap(), &out, true).unwrap();
+    let meta = fs::metadata(&out).unwrap();
+    assert_eq!(meta.permissions().mode() & 0o777, 0o755);
+    assert_eq!(meta.modified().unwrap(), time);
+    
+    // times are only kept when asked
+    let out = dir.join("script_now.sh");
+    fs::write(&out, "echo").unwrap();
+    ops::copy_metadata(&fs::metadata(&script).unwrap(), &out, false).unwrap();
+    assert_ne!(fs::metadata(&out).unwrap().modified().unwrap(), time);
+    
+    // read only files can still be replaced by a later template
+    let out = dir.join("locked_out.txt");
+    fs::write(&out, "locked").unwrap();
+    ops::copy_metadata(&fs::metadata(&locked).unwrap(), &out, false).unwrap();
+    assert_eq!(fs::metadata(&out).unwrap().permissions().mode() & 0o777, 0o644);
+    ops::write(&out, "replaced").unwrap();
+    assert_eq!(fs::read_to_string(&out).unwrap(), "replaced");
+    
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_replaced()
+{
+    use std::io::Write;
+    use projup::file::ops;
+    
+    let dir = temp_dir("links");
+    fs::create_dir_all(&dir).unwrap();
+    let target = dir.join("target.txt");
+    fs::write(&target, "target").unwrap();
+    
+    // links are created with the same target, replacing a file
+    let link = dir.join("link.txt");
+    fs::write(&link, "file").unwrap();
+    ops::symlink("target.txt", &link, false).unwrap();
+    assert_eq!(fs::read_link(&link).unwrap(), PathBuf::from("target.txt"));
+    assert_eq!(fs::read_to_string(&link).unwrap(), "target");
+    
+    // writing over a link replaces the link, not its target
+    ops::write(&link, "written").unwrap();
+    assert!(!fs::symlink_metadata(&link).unwrap().is_symlink());
+    assert_eq!(fs::read_to_string(&link).unwrap(), "written");
+    assert_eq!(fs::read_to_string(&target).unwrap(), "target");
+    
+    ops::symlink("target.txt", &link, false).unwrap();
+    ops::copy(&target, &link).unwrap();
+    assert!(!fs::symlink_metadata(&link).unwrap().is_symlink());
+    
+    ops::symlink("target.txt", &link, false).unwrap();
+    ops::write_with(&link, |mut f| f.write_all(b"streamed")).unwrap();
+    assert!(!fs::symlink_metadata(&link).unwrap().is_symlink());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "target");
+    
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn new_paths()
+{
+    let dir = temp_dir("new");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("a.txt");
+    fs::write(&path, "a").unwrap();
+    
+    assert_eq!(traverse::new_path(&path, |_| false), dir.join("a.txt.new"));
+    // earlier new files are kept
+    fs::write(dir.join("a.txt.new"), "new").unwrap();
+    assert_eq!(traverse::new_path(&path, |_| false), dir.join("a.txt.new.1"));
+    fs::write(dir.join("a.txt.new.1"), "new").unwrap();
+    assert_eq!(traverse::new_path(&path, |_| false), dir.join("a.txt.new.2"));
+    // unless they can be written over
+    assert_eq!(traverse::new_path(&path, |p| p == dir.join("a.txt.new.1")), dir.join("a.txt.new.1"));
+    
+    fs::remove_dir_all(dir).unwrap();
+}
\ No newline at end of file
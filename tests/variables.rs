@@ -0,0 +1,66 @@
+use std::{fs, path::{Path, PathBuf}};
+
+use projup::{data::{Config, ConfigArgs, ConfigError}, error::ProjUpError, file::{self, TemplateFilter}};
+
+fn temp_dir(name: &str) -> PathBuf
+{
+    let dir = std::env::temp_dir().join(format!("projup-variables-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    return dir;
+}
+
+#[test]
+fn replace_variables()
+{
+    let mut args = ConfigArgs::new("my_project");
+    args.map.insert("template", "rust");
+    
+    let r = file::replace_variables("Initial commit of $name:\"pascal\" from $template", &mut args);
+    assert_eq!(r, Ok("Initial commit of MyProject from rust".to_string()));
+    // not references
+    let r = file::replace_variables("Costs 5$ or $-, $name", &mut args);
+    assert_eq!(r, Ok("Costs 5$ or $-, my_project".to_string()));
+    let r = file::replace_variables("Unclosed $name:\"pascal", &mut args);
+    assert_eq!(r, Ok("Unclosed $name:\"pascal".to_string()));
+    
+    let r = file::replace_variables("Made by $author", &mut args);
+    assert_eq!(r, Err(ConfigError::UnknownVariable(1, "author".to_string())));
+}
+
+/// The sorted variables used by the files of `template` with `with_ci` given
+fn template_file_variables(template: &Path, with_ci: &str) -> Result<Vec<String>, ProjUpError>
+{
+    let mut args = ConfigArgs::new("test");
+    args.map.insert("with_ci", with_ci);
+    let content = fs::read_to_string(template.join(".projup")).unwrap();
+    let config = Config::from_content(&content, Some(args)).unwrap();
+    
+    let filter = TemplateFilter::new(template, &config)?;
+    let mut used = Vec::new();
+    file::file_variables(template, template, &filter, &mut used)?;
+    used.sort();
+    return Ok(used);
+}
+
+#[test]
+fn file_variables_filtered()
+{
+    let template = temp_dir("filtered");
+    fs::create_dir_all(template.join("ci")).unwrap();
+    fs::write(template.join(".projup"), "[template]\nname = test\n[binary]\ndata.txt\n[files]\nci/** = $with_ci").unwrap();
+    fs::write(template.join(file::IGNORE_FILE), "ignored.txt").unwrap();
+    fs::write(template.join("main.txt"), "$[if $a]\nA\n$[end]").unwrap();
+    fs::write(template.join("$[for m in $members]$[m].txt"), "$[m]\n$[if $b]\nB\n$[end]").unwrap();
+    // invalid blocks in files that are not parsed
+    fs::write(template.join("ignored.txt"), "$[end]").unwrap();
+    fs::write(template.join("data.txt"), "$[end]").unwrap();
+    fs::write(template.join("ci/build.txt"), "$[end]").unwrap();
+    
+    let used = template_file_variables(&template, "false").unwrap();
+    assert_eq!(used, vec!["a".to_string(), "b".to_string(), "members".to_string()]);
+    // included once the condition is met
+    let used = template_file_variables(&template, "true");
+    assert!(matches!(&used, Err(ProjUpError::InvalidTemplateFile(p, _)) if *p == template.join("ci/build.txt")), "{used:?}");
+    
+    fs::remove_dir_all(&template).unwrap();
+}
\ No newline at end of file
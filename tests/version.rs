@@ -1,6 +1,6 @@
 use std::str::FromStr;
 
-use projup::data::{ParseVersionError, Version};
+use projup::data::{ParseVersionError, PreRelease, Version, VersionReq};
 
 #[test]
 fn version_from_string()
@@ -32,4 +32,92 @@ fn version_from_string()
     let s = "1 . 2 . 3";
     let v = Version::from_str(s);
     assert!(v.is_err_and(|e| e.is_int()));
+}
+
+#[test]
+fn version_pre_release_build()
+{
+    let v = Version::from_str("1.0.0-beta.2+build5").unwrap();
+    assert_eq!(v.pre, vec![PreRelease::Alpha("beta".to_string()), PreRelease::Numeric(2)]);
+    assert_eq!(v.build, vec!["build5".to_string()]);
+    assert_eq!(v.to_string(), "1.0.0-beta.2+build5");
+    assert_eq!(Version::from_str("2.1+exp.sha-5114f85").unwrap().to_string(), "2.1.0+exp.sha-5114f85");
+    
+    assert_eq!(Version::from_str("1.0.0-"), Err(ParseVersionError::PreRelease(String::new())));
+    assert_eq!(Version::from_str("1.0.0-a..b"), Err(ParseVersionError::PreRelease(String::new())));
+    assert_eq!(Version::from_str("1.0.0-01"), Err(ParseVersionError::PreRelease("01".to_string())));
+    assert_eq!(Version::from_str("1.0.0-a_b"), Err(ParseVersionError::PreRelease("a_b".to_string())));
+    assert_eq!(Version::from_str("1.0.0+"), Err(ParseVersionError::Build(String::new())));
+    assert_eq!(Version::from_str("1.0.0+a+b"), Err(ParseVersionError::Build("a+b".to_string())));
+    // leading zeros are allowed in build metadata
+    assert!(Version::from_str("1.0.0+001").is_ok());
+    assert!(Version::from_str("1.x.0-beta").is_err_and(|e| e.is_int()));
+    
+    // precedence from the semantic versioning specification
+    let ordered = ["1.0.0-alpha", "1.0.0-alpha.1", "1.0.0-alpha.beta", "1.0.0-beta", "1.0.0-beta.2",
+        "1.0.0-beta.11", "1.0.0-rc.1", "1.0.0", "1.0.1", "1.1.0", "2.0.0"];
+    for w in ordered.windows(2)
+    {
+        let a = Version::from_str(w[0]).unwrap();
+        let b = Version::from_str(w[1]).unwrap();
+        assert!(a < b, "{a} < {b}");
+    }
+    
+    // build metadata does not affect precedence
+    let a = Version::from_str("1.0.0+a").unwrap();
+    let b = Version::from_str("1.0.0+b").unwrap();
+    assert!(a.cmp_precedence(&b).is_eq());
+    assert_ne!(a, b);
+}
+
+fn matches(req: &str, version: &str) -> bool
+{
+    return VersionReq::from_str(req).unwrap().matches(&Version::from_str(version).unwrap());
+}
+
+#[test]
+fn version_requirements()
+{
+    // caret is the default
+    assert!(matches("1.2", "1.2.0"));
+    assert!(matches("1.2", "1.9.4"));
+    assert!(!matches("1.2", "1.1.9"));
+    assert!(!matches("^1.2", "2.0.0"));
+    assert!(matches("^0.2.3", "0.2.9"));
+    assert!(!matches("^0.2.3", "0.3.0"));
+    assert!(matches("^0.0.3", "0.0.3"));
+    assert!(!matches("^0.0.3", "0.0.4"));
+    assert!(matches("^0", "0.9.0"));
+    
+    assert!(matches("~1.2.3", "1.2.7"));
+    assert!(!matches("~1.2.3", "1.3.0"));
+    assert!(matches("~1", "1.5.0"));
+    assert!(!matches("~1", "2.0.0"));
+    
+    assert!(matches("=1.2", "1.2.5"));
+    assert!(!matches("=1.2.0", "1.2.5"));
+    assert!(matches(">=2", "2.0.0"));
+    assert!(matches(">=2", "10.0.0"));
+    assert!(!matches(">=2", "1.9.9"));
+    assert!(!matches(">1.2", "1.2.9"));
+    assert!(matches(">1.2", "1.3.0"));
+    assert!(matches("<=1.2", "1.2.9"));
+    assert!(!matches("<1.2", "1.2.0"));
+    
+    assert!(matches(">=1.2, <1.5", "1.4.0"));
+    assert!(!matches(">=1.2, <1.5", "1.5.0"));
+    assert!(matches("*", "0.0.1"));
+    
+    // pre-releases only match requirements for the same version
+    assert!(!matches("^1.0", "1.2.0-beta"));
+    assert!(matches("^1.2.0-beta", "1.2.0-beta.2"));
+    assert!(matches("^1.2.0-beta", "1.2.5"));
+    assert!(!matches("^1.2.0-beta", "1.3.0-alpha"));
+    assert!(matches("=1.2.0-rc.1", "1.2.0-rc.1+build"));
+    assert!(!matches("=1.2.0-rc.1", "1.2.0"));
+    assert!(matches(">1.0.0-alpha", "1.0.0-beta"));
+    
+    assert!(VersionReq::from_str("1.2.3.4").is_err());
+    assert!(VersionReq::from_str(">>1").is_err());
+    assert_eq!(VersionReq::from_str(">= 1.2,~2").unwrap().to_string(), ">=1.2, ~2");
 }
\ No newline at end of file